@@ -0,0 +1,53 @@
+#![feature(test)]
+
+extern crate test;
+
+use advent_of_code_2023::d04_scratchcards::{self, parse_card, points, Card};
+use test::Bencher;
+
+fn task_lines() -> Vec<String> {
+    advent_of_code_2023::utils::read_lines(d04_scratchcards::solution().part_1.task).collect()
+}
+
+#[bench]
+fn sorted_matching(b: &mut Bencher) {
+    let cards: Vec<_> = task_lines().iter().map(|line| parse_card(line)).collect();
+    b.iter(|| {
+        cards
+            .iter()
+            .map(|(_, winning, actual)| points(winning.clone(), actual.clone()))
+            .sum::<u32>()
+    });
+}
+
+#[bench]
+fn bitset_matching(b: &mut Bencher) {
+    let cards: Vec<_> = task_lines()
+        .iter()
+        .map(|line| Card::from_line(line))
+        .collect();
+    b.iter(|| cards.iter().map(Card::matches).sum::<usize>());
+}
+
+#[bench]
+fn sorted_parse_and_match(b: &mut Bencher) {
+    let lines = task_lines();
+    b.iter(|| {
+        lines
+            .iter()
+            .map(|line| parse_card(line))
+            .map(|(_, winning, actual)| points(winning, actual))
+            .sum::<u32>()
+    });
+}
+
+#[bench]
+fn bitset_parse_and_match(b: &mut Bencher) {
+    let lines = task_lines();
+    b.iter(|| {
+        lines
+            .iter()
+            .map(|line| Card::from_line(line).matches())
+            .sum::<usize>()
+    });
+}
@@ -1,62 +1,372 @@
+use std::collections::VecDeque;
+
 use crate::utils::{Day, Task};
 
-pub fn p1_calibration_total(filename: &str) -> u32 {
-    crate::utils::read_lines(filename)
-        .map(|line| {
-            let left = line
-                .chars()
-                .find(|c| c.is_numeric())
-                .and_then(|c| c.to_digit(10))
-                .unwrap();
-            let right = line
-                .chars()
-                .rfind(|c| c.is_numeric())
-                .and_then(|c| c.to_digit(10))
-                .unwrap();
+pub const NUMERALS: [(&str, u32); 10] = [
+    ("0", 0),
+    ("1", 1),
+    ("2", 2),
+    ("3", 3),
+    ("4", 4),
+    ("5", 5),
+    ("6", 6),
+    ("7", 7),
+    ("8", 8),
+    ("9", 9),
+];
 
-            10 * left + right
-        })
-        .sum()
+pub const ENGLISH: [(&str, u32); 9] = [
+    ("one", 1),
+    ("two", 2),
+    ("three", 3),
+    ("four", 4),
+    ("five", 5),
+    ("six", 6),
+    ("seven", 7),
+    ("eight", 8),
+    ("nine", 9),
+];
+
+pub const GERMAN: [(&str, u32); 9] = [
+    ("eins", 1),
+    ("zwei", 2),
+    ("drei", 3),
+    ("vier", 4),
+    ("fünf", 5),
+    ("sechs", 6),
+    ("sieben", 7),
+    ("acht", 8),
+    ("neun", 9),
+];
+
+// Aho-Corasick automaton with the failure links folded into a full transition table.
+struct Automaton {
+    delta: Vec<[usize; 256]>,
+    // indices of the patterns ending in each state
+    outputs: Vec<Vec<usize>>,
 }
 
-pub fn p2_calibration_total(filename: &str) -> u32 {
-    let digit_words = [
-        "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
-    ];
-
-    crate::utils::read_lines(filename)
-        .map(|line| {
-            let (mut left_index, mut left) = line
-                .char_indices()
-                .find(|(_, c)| c.is_numeric())
-                .map_or((line.len(), 0), |(i, c)| (i, c.to_digit(10).unwrap()));
-
-            let (mut right_index, mut right) = line
-                .char_indices()
-                .rfind(|(_, c)| c.is_numeric())
-                .map_or((0, 0), |(i, c)| (i + 1, c.to_digit(10).unwrap()));
-
-            for (n, &digit_word) in digit_words.iter().enumerate() {
-                if let Some(new_left_index) = line
-                    .get(0..left_index)
-                    .and_then(|line| line.find(digit_word))
-                {
-                    left_index = new_left_index + digit_word.len();
-                    left = 1 + n as u32;
+impl Automaton {
+    fn new<'a>(patterns: impl IntoIterator<Item = &'a [u8]>) -> Self {
+        let mut delta = vec![[0; 256]];
+        let mut outputs = vec![vec![]];
+
+        for (p, pattern) in patterns.into_iter().enumerate() {
+            let mut state = 0;
+            for &b in pattern {
+                if delta[state][b as usize] == 0 {
+                    delta.push([0; 256]);
+                    outputs.push(vec![]);
+                    delta[state][b as usize] = delta.len() - 1;
                 }
+                state = delta[state][b as usize];
+            }
+            outputs[state].push(p);
+        }
+
+        let mut fail = vec![0; delta.len()];
+        let mut queue = delta[0]
+            .iter()
+            .copied()
+            .filter(|&s| s != 0)
+            .collect::<VecDeque<_>>();
 
-                if let Some(new_right_index) = line
-                    .get(right_index..)
-                    .and_then(|line| line.rfind(digit_word))
-                {
-                    right_index += new_right_index;
-                    right = 1 + n as u32;
+        while let Some(state) = queue.pop_front() {
+            let inherited = outputs[fail[state]].clone();
+            outputs[state].extend(inherited);
+
+            let fallback = delta[fail[state]];
+            for (next, fallback) in delta[state].iter_mut().zip(fallback) {
+                if *next == 0 {
+                    *next = fallback;
+                } else {
+                    fail[*next] = fallback;
+                    queue.push_back(*next);
                 }
             }
+        }
+
+        Self { delta, outputs }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct DigitMatch {
+    pub value: u32,
+    // byte range of the matched word in the line
+    pub start: usize,
+    pub end: usize,
+}
+
+pub struct DigitScanner {
+    words: Vec<(Vec<u8>, u32)>,
+    forward: Automaton,
+    // built from reversed words, runs over the line from its end
+    backward: Automaton,
+    max_len: usize,
+}
+
+impl DigitScanner {
+    pub fn new<'a>(vocabulary: impl IntoIterator<Item = &'a (&'a str, u32)>) -> Self {
+        let words = vocabulary
+            .into_iter()
+            .filter(|(word, _)| !word.is_empty())
+            .map(|&(word, value)| (word.as_bytes().to_vec(), value))
+            .collect::<Vec<_>>();
+        let reversed = words
+            .iter()
+            .map(|(word, _)| word.iter().rev().copied().collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+
+        Self {
+            forward: Automaton::new(words.iter().map(|(word, _)| word.as_slice())),
+            backward: Automaton::new(reversed.iter().map(Vec::as_slice)),
+            max_len: words.iter().map(|(word, _)| word.len()).max().unwrap_or(0),
+            words,
+        }
+    }
+
+    // Leftmost starting word, the longest one on ties.
+    pub fn first(&self, line: &str) -> Option<DigitMatch> {
+        let mut state = 0;
+        let mut best: Option<DigitMatch> = None;
+
+        for (i, &b) in line.as_bytes().iter().enumerate() {
+            if best.is_some_and(|m| i >= m.start + self.max_len) {
+                break;
+            }
+            state = self.forward.delta[state][b as usize];
+            for &p in &self.forward.outputs[state] {
+                let (word, value) = &self.words[p];
+                let found = DigitMatch {
+                    value: *value,
+                    start: i + 1 - word.len(),
+                    end: i + 1,
+                };
+                if best.is_none_or(|m| (found.start, m.end) < (m.start, found.end)) {
+                    best = Some(found);
+                }
+            }
+        }
+
+        best
+    }
+
+    // Rightmost ending word, the longest one on ties.
+    pub fn last(&self, line: &str) -> Option<DigitMatch> {
+        let mut state = 0;
+        let mut best: Option<DigitMatch> = None;
+
+        for (i, &b) in line.as_bytes().iter().enumerate().rev() {
+            if best.is_some_and(|m| i + self.max_len < m.end) {
+                break;
+            }
+            state = self.backward.delta[state][b as usize];
+            for &p in &self.backward.outputs[state] {
+                let (word, value) = &self.words[p];
+                let found = DigitMatch {
+                    value: *value,
+                    start: i,
+                    end: i + word.len(),
+                };
+                if best.is_none_or(|m| (m.end, found.start) < (found.end, m.start)) {
+                    best = Some(found);
+                }
+            }
+        }
+
+        best
+    }
+
+    pub fn first_and_last(&self, line: &str) -> Option<(DigitMatch, DigitMatch)> {
+        Some((self.first(line)?, self.last(line)?))
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ReportFormat {
+    Csv,
+    Json,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct LineReport {
+    // 1-based
+    pub number: usize,
+    pub line: String,
+    // `None` if the line has no digit at all
+    pub digits: Option<(DigitMatch, DigitMatch)>,
+}
+
+impl LineReport {
+    pub fn value(&self) -> Option<u32> {
+        self.digits
+            .map(|(first, last)| 10 * first.value + last.value)
+    }
+
+    pub fn token(&self, digit: DigitMatch) -> &str {
+        &self.line[digit.start..digit.end]
+    }
+
+    pub fn diagnostic(&self) -> &'static str {
+        match self.digits {
+            Some(_) => "",
+            None => "no digit",
+        }
+    }
+}
 
-            10 * left + right
-        })
-        .sum()
+pub struct CalibrationReport {
+    lines: Vec<LineReport>,
+}
+
+impl CalibrationReport {
+    pub fn new(filename: &str, scanner: &DigitScanner) -> Self {
+        let lines = crate::utils::read_lines(filename)
+            .enumerate()
+            .map(|(i, line)| LineReport {
+                number: i + 1,
+                digits: scanner.first_and_last(&line),
+                line,
+            })
+            .collect();
+        Self { lines }
+    }
+
+    pub fn lines(&self) -> &[LineReport] {
+        &self.lines
+    }
+
+    pub fn total(&self) -> u32 {
+        self.lines.iter().flat_map(LineReport::value).sum()
+    }
+
+    pub fn missing(&self) -> usize {
+        self.lines.iter().filter(|l| l.digits.is_none()).count()
+    }
+
+    // One row per line, followed by a `total` row.
+    pub fn to_csv(&self) -> String {
+        fn field(s: &str) -> String {
+            match s.contains([',', '"', '\n']) {
+                true => format!("\"{}\"", s.replace('"', "\"\"")),
+                false => s.to_string(),
+            }
+        }
+
+        let mut csv =
+            String::from("line,text,first,first_start,last,last_start,value,diagnostic\n");
+        for report in &self.lines {
+            let (first, first_start, last, last_start) = match report.digits {
+                Some((first, last)) => (
+                    report.token(first),
+                    first.start.to_string(),
+                    report.token(last),
+                    last.start.to_string(),
+                ),
+                None => ("", String::new(), "", String::new()),
+            };
+            let value = report.value().map_or(String::new(), |v| v.to_string());
+            csv.push_str(&format!(
+                "{},{},{},{first_start},{},{last_start},{value},{}\n",
+                report.number,
+                field(&report.line),
+                field(first),
+                field(last),
+                report.diagnostic(),
+            ));
+        }
+        csv.push_str(&format!(
+            "total,,,,,,{},{} of {} lines without digits\n",
+            self.total(),
+            self.missing(),
+            self.lines.len()
+        ));
+        csv
+    }
+
+    pub fn to_json(&self) -> String {
+        fn string(s: &str) -> String {
+            let escaped: String = s
+                .chars()
+                .map(|c| match c {
+                    '"' => "\\\"".to_string(),
+                    '\\' => "\\\\".to_string(),
+                    c if c.is_control() => format!("\\u{:04x}", c as u32),
+                    c => c.to_string(),
+                })
+                .collect();
+            format!("\"{escaped}\"")
+        }
+
+        fn digit(report: &LineReport, digit: DigitMatch) -> String {
+            format!(
+                "{{\"token\": {}, \"value\": {}, \"start\": {}, \"end\": {}}}",
+                string(report.token(digit)),
+                digit.value,
+                digit.start,
+                digit.end
+            )
+        }
+
+        let lines = self
+            .lines
+            .iter()
+            .map(|report| {
+                let details = match report.digits {
+                    Some((first, last)) => format!(
+                        "\"first\": {}, \"last\": {}, \"value\": {}",
+                        digit(report, first),
+                        digit(report, last),
+                        10 * first.value + last.value
+                    ),
+                    None => format!("\"diagnostic\": {}", string(report.diagnostic())),
+                };
+                format!(
+                    "    {{\"line\": {}, \"text\": {}, {details}}}",
+                    report.number,
+                    string(&report.line)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(",\n");
+
+        format!(
+            "{{\n  \"lines\": [\n{lines}\n  ],\n  \"summary\": {{\"lines\": {}, \"missing\": {}, \"total\": {}}}\n}}\n",
+            self.lines.len(),
+            self.missing(),
+            self.total()
+        )
+    }
+
+    pub fn render(&self, format: ReportFormat) -> String {
+        match format {
+            ReportFormat::Csv => self.to_csv(),
+            ReportFormat::Json => self.to_json(),
+        }
+    }
+}
+
+pub fn print_report(filename: &str, scanner: &DigitScanner, format: ReportFormat) {
+    print!(
+        "{}",
+        CalibrationReport::new(filename, scanner).render(format)
+    );
+}
+
+fn calibration_total(filename: &str, scanner: &DigitScanner) -> u32 {
+    CalibrationReport::new(filename, scanner).total()
+}
+
+pub fn p1_calibration_total(filename: &str) -> u32 {
+    calibration_total(filename, &DigitScanner::new(&NUMERALS))
+}
+
+pub fn p2_calibration_total(filename: &str) -> u32 {
+    calibration_total(
+        filename,
+        &DigitScanner::new(NUMERALS.iter().chain(&ENGLISH)),
+    )
 }
 
 pub fn solution() -> Day<u32, u32> {
@@ -89,4 +399,115 @@ mod d01_tests {
         let res = solution().part_2.run_example(0);
         assert_eq!(res, 281);
     }
+
+    #[test]
+    fn scanner_test() {
+        let scanner = DigitScanner::new(NUMERALS.iter().chain(&ENGLISH));
+        let (first, last) = scanner.first_and_last("xeightwo").unwrap();
+        assert_eq!(
+            DigitMatch {
+                value: 8,
+                start: 1,
+                end: 6
+            },
+            first
+        );
+        assert_eq!(
+            DigitMatch {
+                value: 2,
+                start: 5,
+                end: 8
+            },
+            last
+        );
+        assert_eq!(None, scanner.first_and_last("abc"));
+
+        let extra = [("zero", 0), ("ten", 10), ("seventeen", 17)];
+        let scanner = DigitScanner::new(ENGLISH.iter().chain(&extra));
+        let (first, last) = scanner.first_and_last("zeroseventeen").unwrap();
+        assert_eq!(
+            DigitMatch {
+                value: 0,
+                start: 0,
+                end: 4
+            },
+            first
+        );
+        assert_eq!(
+            DigitMatch {
+                value: 17,
+                start: 4,
+                end: 13
+            },
+            last
+        );
+
+        let scanner = DigitScanner::new(NUMERALS.iter().chain(&GERMAN));
+        let (first, last) = scanner.first_and_last("fünfzweiacht").unwrap();
+        assert_eq!(
+            DigitMatch {
+                value: 5,
+                start: 0,
+                end: 5
+            },
+            first
+        );
+        assert_eq!(
+            DigitMatch {
+                value: 8,
+                start: 9,
+                end: 13
+            },
+            last
+        );
+
+        assert_eq!(281, p2_calibration_total("./inputs/day_01/example_02.txt"));
+    }
+
+    #[test]
+    fn report_test() {
+        let scanner = DigitScanner::new(NUMERALS.iter().chain(&ENGLISH));
+        let report = CalibrationReport::new("./inputs/day_01/example_02.txt", &scanner);
+
+        assert_eq!(281, report.total());
+        assert_eq!(0, report.missing());
+
+        let line = &report.lines()[1];
+        let (first, last) = line.digits.unwrap();
+        assert_eq!(("eight", 0), (line.token(first), first.start));
+        assert_eq!(("three", 7), (line.token(last), last.start));
+        assert_eq!(Some(83), line.value());
+
+        let csv = report.to_csv();
+        assert_eq!(
+            Some("2,eightwothree,eight,0,three,7,83,"),
+            csv.lines().nth(2)
+        );
+        assert_eq!(
+            Some("total,,,,,,281,0 of 7 lines without digits"),
+            csv.lines().last()
+        );
+
+        let json = report.to_json();
+        assert!(json.contains("\"summary\": {\"lines\": 7, \"missing\": 0, \"total\": 281}"));
+    }
+
+    #[test]
+    fn no_digit_test() {
+        let scanner = DigitScanner::new(&NUMERALS);
+        let report = CalibrationReport {
+            lines: vec![LineReport {
+                number: 1,
+                line: "a\"b".to_string(),
+                digits: scanner.first_and_last("a\"b"),
+            }],
+        };
+
+        assert_eq!(0, report.total());
+        assert_eq!(1, report.missing());
+        assert!(report.to_csv().contains("1,\"a\"\"b\",,,,,,no digit"));
+        assert!(report
+            .to_json()
+            .contains("{\"line\": 1, \"text\": \"a\\\"b\", \"diagnostic\": \"no digit\"}"));
+    }
 }
@@ -1,36 +1,359 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use itertools::Itertools;
+
 use crate::utils::{Day, Task};
 
-fn parse_game_line(line: &str) -> (usize, usize, usize, usize) {
-    let (game_id, attempts) = line.split_once(':').unwrap();
-    let game_id = game_id.split_once(' ').unwrap().1.parse().unwrap();
-
-    let (r, g, b) = attempts
-        .split(';')
-        .flat_map(|attempt| attempt.split(','))
-        .flat_map(|res| res.trim().split_once(' '))
-        .map(|(n, color)| (n.parse::<usize>().unwrap(), color))
-        .fold((0, 0, 0), |(r, g, b), (n, color)| match color {
-            "red" => (r.max(n), g, b),
-            "green" => (r, g.max(n), b),
-            "blue" => (r, g, b.max(n)),
-            _ => unreachable!(),
+// cube counts by colour, colours not present count as zero
+pub type Cubes = BTreeMap<String, usize>;
+pub type Draw = Cubes;
+pub type Bag = Cubes;
+
+pub fn bag(contents: &[(&str, usize)]) -> Bag {
+    contents
+        .iter()
+        .map(|&(color, n)| (color.to_string(), n))
+        .collect()
+}
+
+fn count(cubes: &Cubes, color: &str) -> usize {
+    cubes.get(color).copied().unwrap_or(0)
+}
+
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct ColorStats {
+    // number of draws the colour was shown in
+    pub draws: usize,
+    pub min: usize,
+    pub max: usize,
+    pub total: usize,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Game {
+    pub id: usize,
+    pub draws: Vec<Draw>,
+}
+
+impl Game {
+    pub fn from_line(line: &str) -> Option<Self> {
+        let (game_id, draws) = line.split_once(':')?;
+        let id = game_id.strip_prefix("Game ")?.parse().ok()?;
+
+        let draws = draws
+            .split(';')
+            .map(|draw| {
+                draw.split(',')
+                    .filter(|cubes| !cubes.trim().is_empty())
+                    .map(|cubes| {
+                        let (n, color) = cubes.trim().split_once(' ')?;
+                        Some((color.to_string(), n.parse::<usize>().ok()?))
+                    })
+                    .try_fold(Draw::new(), |mut draw, cubes| {
+                        let (color, n) = cubes?;
+                        *draw.entry(color).or_default() += n;
+                        Some(draw)
+                    })
+            })
+            .collect::<Option<_>>()?;
+
+        Some(Self { id, draws })
+    }
+
+    pub fn colors(&self) -> BTreeSet<&str> {
+        self.draws
+            .iter()
+            .flat_map(|draw| draw.keys())
+            .map(String::as_str)
+            .collect()
+    }
+
+    pub fn is_possible_with(&self, bag: &Bag) -> bool {
+        self.minimal_bag()
+            .iter()
+            .all(|(color, &n)| n <= count(bag, color))
+    }
+
+    pub fn minimal_bag(&self) -> Bag {
+        self.draws.iter().fold(Bag::new(), |mut bag, draw| {
+            for (color, &n) in draw {
+                let max = bag.entry(color.clone()).or_default();
+                *max = n.max(*max);
+            }
+            bag
+        })
+    }
+
+    pub fn stats(&self, color: &str) -> ColorStats {
+        self.draws.iter().flat_map(|draw| draw.get(color)).fold(
+            ColorStats::default(),
+            |stats, &n| ColorStats {
+                draws: stats.draws + 1,
+                min: if stats.draws == 0 {
+                    n
+                } else {
+                    stats.min.min(n)
+                },
+                max: stats.max.max(n),
+                total: stats.total + n,
+            },
+        )
+    }
+
+    // Product of the minimal counts of the given colours.
+    pub fn power(&self, colors: &[&str]) -> usize {
+        let bag = self.minimal_bag();
+        colors.iter().map(|color| count(&bag, color)).product()
+    }
+}
+
+// Bags of at most `budget` cubes holding at least `lower` of every colour and, for every
+// excluded game, fewer cubes of some colour than its minimal bag.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct BagConstraints {
+    pub lower: Bag,
+    // excluded game id and its minimal bag
+    pub excluded: Vec<(usize, Bag)>,
+    pub budget: usize,
+}
+
+impl BagConstraints {
+    fn fits(minimal: &Bag, bag: &Bag) -> bool {
+        minimal.iter().all(|(color, &n)| n <= count(bag, color))
+    }
+
+    pub fn contains(&self, bag: &Bag) -> bool {
+        bag.values().sum::<usize>() <= self.budget
+            && Self::fits(&self.lower, bag)
+            && self
+                .excluded
+                .iter()
+                .all(|(_, minimal)| !Self::fits(minimal, bag))
+    }
+
+    // Every bag satisfying the constraints, their number grows exponentially with the colours.
+    pub fn bags(&self) -> Vec<Bag> {
+        fn fill(
+            lower: &[usize],
+            budget: usize,
+            counts: &mut Vec<usize>,
+            found: &mut impl FnMut(&[usize]),
+        ) {
+            let Some((&min, rest)) = lower.split_first() else {
+                return found(counts);
+            };
+            let reserved = rest.iter().sum::<usize>();
+            for n in min..=budget.saturating_sub(reserved) {
+                counts.push(n);
+                fill(rest, budget - n, counts, found);
+                counts.pop();
+            }
+        }
+
+        let lower = self.lower.values().copied().collect_vec();
+        if lower.iter().sum::<usize>() > self.budget {
+            return vec![];
+        }
+
+        let mut bags = vec![];
+        fill(&lower, self.budget, &mut vec![], &mut |counts| {
+            let bag = self
+                .lower
+                .keys()
+                .cloned()
+                .zip(counts.iter().copied())
+                .collect();
+            if self.contains(&bag) {
+                bags.push(bag);
+            }
         });
+        bags
+    }
+}
 
-    (game_id, r, g, b)
+pub struct BagSolver {
+    colors: Vec<String>,
+    // game id and its minimal bag, indexed like `colors`
+    games: Vec<(usize, Vec<usize>)>,
 }
 
-pub fn possible_games_sum(filename: &str, contents: (usize, usize, usize)) -> usize {
+impl BagSolver {
+    pub fn new(games: &[Game]) -> Self {
+        let colors = games
+            .iter()
+            .flat_map(Game::colors)
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .map(str::to_string)
+            .collect_vec();
+
+        let games = games
+            .iter()
+            .map(|game| {
+                let bag = game.minimal_bag();
+                let counts = colors.iter().map(|color| count(&bag, color)).collect();
+                (game.id, counts)
+            })
+            .collect();
+
+        Self { colors, games }
+    }
+
+    fn to_bag(&self, counts: &[usize]) -> Bag {
+        self.colors
+            .iter()
+            .cloned()
+            .zip(counts.iter().copied())
+            .collect()
+    }
+
+    fn is_possible(minimal: &[usize], counts: &[usize]) -> bool {
+        minimal.iter().zip(counts).all(|(m, n)| m <= n)
+    }
+
+    pub fn possible_games(&self, bag: &Bag) -> BTreeSet<usize> {
+        let counts = self
+            .colors
+            .iter()
+            .map(|color| count(bag, color))
+            .collect_vec();
+        self.games
+            .iter()
+            .filter(|(_, minimal)| Self::is_possible(minimal, &counts))
+            .map(|&(id, _)| id)
+            .collect()
+    }
+
+    // Constraints on bags of at most `budget` cubes under which exactly the `possible` games are
+    // possible, `None` if there is no such bag.
+    pub fn consistent_bags(
+        &self,
+        possible: &BTreeSet<usize>,
+        budget: usize,
+    ) -> Option<BagConstraints> {
+        let (included, excluded): (Vec<_>, Vec<_>) =
+            self.games.iter().partition(|(id, _)| possible.contains(id));
+
+        let lower = (0..self.colors.len())
+            .map(|i| {
+                included
+                    .iter()
+                    .map(|(_, minimal)| minimal[i])
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect_vec();
+
+        // the lower bounds themselves are a consistent bag unless some excluded game fits them
+        if lower.iter().sum::<usize>() > budget
+            || excluded
+                .iter()
+                .any(|(_, minimal)| Self::is_possible(minimal, &lower))
+        {
+            return None;
+        }
+
+        Some(BagConstraints {
+            lower: self.to_bag(&lower),
+            excluded: excluded
+                .iter()
+                .map(|(id, minimal)| (*id, self.to_bag(minimal)))
+                .collect(),
+            budget,
+        })
+    }
+
+    // Bag of at most `budget` cubes making the most games possible, the smallest one on ties.
+    pub fn best_bag(&self, budget: usize) -> (Bag, BTreeSet<usize>) {
+        struct Best {
+            counts: Vec<usize>,
+            games: usize,
+            cubes: usize,
+        }
+
+        fn search(
+            games: &[&Vec<usize>],
+            color: usize,
+            counts: &mut Vec<usize>,
+            budget: usize,
+            best: &mut Best,
+        ) {
+            let cubes = counts.iter().sum::<usize>();
+            if (games.len(), best.cubes) <= (best.games, cubes) {
+                return;
+            }
+
+            let Some(&first) = games.first() else {
+                return;
+            };
+            if color == first.len() {
+                *best = Best {
+                    counts: counts.clone(),
+                    games: games.len(),
+                    cubes,
+                };
+                return;
+            }
+
+            // only the counts some game needs exactly are worth trying
+            let candidates = games
+                .iter()
+                .map(|minimal| minimal[color])
+                .filter(|&n| n <= budget)
+                .chain([0])
+                .sorted_unstable_by(|a, b| b.cmp(a))
+                .dedup();
+
+            for n in candidates {
+                let left = games
+                    .iter()
+                    .copied()
+                    .filter(|minimal| minimal[color] <= n)
+                    .collect_vec();
+                counts.push(n);
+                search(&left, color + 1, counts, budget - n, best);
+                counts.pop();
+            }
+        }
+
+        let mut best = Best {
+            counts: vec![0; self.colors.len()],
+            games: 0,
+            cubes: 0,
+        };
+        let games = self.games.iter().map(|(_, minimal)| minimal).collect_vec();
+        search(&games, 0, &mut vec![], budget, &mut best);
+
+        let bag = self.to_bag(&best.counts);
+        let possible = self.possible_games(&bag);
+        (bag, possible)
+    }
+}
+
+pub fn parse_games(filename: &str) -> Vec<Game> {
     crate::utils::read_lines(filename)
-        .map(|line| parse_game_line(&line))
-        .filter(|&(_, r, g, b)| r <= contents.0 && g <= contents.1 && b <= contents.2)
-        .map(|res| res.0)
+        .map(|line| Game::from_line(&line).unwrap_or_else(|| panic!("malformed game '{line}'")))
+        .collect()
+}
+
+pub fn possible_games_sum(filename: &str, contents: (usize, usize, usize)) -> usize {
+    let bag = bag(&[
+        ("red", contents.0),
+        ("green", contents.1),
+        ("blue", contents.2),
+    ]);
+
+    parse_games(filename)
+        .iter()
+        .filter(|game| game.is_possible_with(&bag))
+        .map(|game| game.id)
         .sum()
 }
 
 pub fn power_of_sets(filename: &str) -> usize {
-    crate::utils::read_lines(filename)
-        .map(|line| parse_game_line(&line))
-        .map(|(_, r, g, b)| r * g * b)
+    parse_games(filename)
+        .iter()
+        .map(|game| game.power(&["red", "green", "blue"]))
         .sum()
 }
 
@@ -68,4 +391,94 @@ mod d02_tests {
         let res = solution().part_2.run_example(0);
         assert_eq!(res, 2286);
     }
+
+    #[test]
+    fn game_test() {
+        let game =
+            Game::from_line("Game 7: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green; 5 violet")
+                .unwrap();
+
+        assert_eq!(7, game.id);
+        assert_eq!(4, game.draws.len());
+        assert_eq!(
+            BTreeSet::from(["blue", "green", "red", "violet"]),
+            game.colors()
+        );
+        assert_eq!(
+            bag(&[("blue", 6), ("green", 2), ("red", 4), ("violet", 5)]),
+            game.minimal_bag()
+        );
+        assert_eq!(
+            ColorStats {
+                draws: 2,
+                min: 3,
+                max: 6,
+                total: 9
+            },
+            game.stats("blue")
+        );
+        assert_eq!(ColorStats::default(), game.stats("black"));
+
+        assert_eq!(48, game.power(&["red", "green", "blue"]));
+        assert_eq!(5, game.power(&["violet"]));
+        assert_eq!(0, game.power(&["black"]));
+
+        assert!(!game.is_possible_with(&bag(&[("red", 12), ("green", 13), ("blue", 14)])));
+        assert!(game.is_possible_with(&bag(&[
+            ("red", 4),
+            ("green", 2),
+            ("blue", 6),
+            ("violet", 5)
+        ])));
+
+        assert_eq!(None, Game::from_line("Game 1: two red"));
+    }
+
+    #[test]
+    fn bag_solver_test() {
+        let solver = BagSolver::new(&parse_games(solution().part_1.examples[0]));
+        let part_1 = bag(&[("red", 12), ("green", 13), ("blue", 14)]);
+        let possible = BTreeSet::from([1, 2, 5]);
+        assert_eq!(possible, solver.possible_games(&part_1));
+
+        let constraints = solver.consistent_bags(&possible, 39).unwrap();
+        assert_eq!(
+            bag(&[("red", 6), ("green", 3), ("blue", 6)]),
+            constraints.lower
+        );
+        assert_eq!(
+            vec![3, 4],
+            constraints.excluded.iter().map(|&(id, _)| id).collect_vec()
+        );
+        assert!(constraints.contains(&part_1));
+        assert!(constraints.contains(&bag(&[("red", 6), ("green", 3), ("blue", 6)])));
+        assert!(!constraints.contains(&bag(&[("red", 14), ("green", 3), ("blue", 15)])));
+        assert!(!constraints.contains(&bag(&[("red", 20), ("green", 13), ("blue", 14)])));
+
+        let bags = constraints.bags();
+        assert!(bags.contains(&part_1));
+        assert!(
+            bags.iter()
+                .all(|bag| solver.possible_games(bag) == possible
+                    && bag.values().sum::<usize>() <= 39)
+        );
+        assert_eq!(None, solver.consistent_bags(&possible, 14));
+        assert_eq!(None, solver.consistent_bags(&BTreeSet::from([3]), 100));
+
+        assert_eq!(
+            (
+                bag(&[("red", 14), ("green", 3), ("blue", 15)]),
+                BTreeSet::from([1, 2, 4, 5])
+            ),
+            solver.best_bag(39)
+        );
+        assert_eq!(5, solver.best_bag(48).1.len());
+        assert_eq!(
+            (
+                bag(&[("red", 0), ("green", 0), ("blue", 0)]),
+                BTreeSet::new()
+            ),
+            solver.best_bag(5)
+        );
+    }
 }
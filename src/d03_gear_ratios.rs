@@ -1,3 +1,5 @@
+use itertools::Itertools;
+
 use crate::utils::{Day, Task};
 
 fn first_integer_boundaries(line: &[char], offset: usize) -> Option<(usize, usize)> {
@@ -23,99 +25,244 @@ fn parse_integer(line: &[char]) -> u64 {
         .1 as u64
 }
 
-pub fn sum_part_numbers(filename: &str) -> u64 {
-    let lines: Vec<Vec<_>> = crate::utils::read_lines(filename)
-        .map(|line| line.chars().collect())
-        .collect();
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Number {
+    pub value: u64,
+    pub y: usize,
+    // inclusive span of digits
+    pub start: usize,
+    pub end: usize,
+}
 
-    let empty = vec![];
-    let mut sum = 0;
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Symbol {
+    pub char: char,
+    pub x: usize,
+    pub y: usize,
+}
 
-    for (i, line) in lines.iter().enumerate() {
-        let mut offset = 0;
-        let higher_line = i.checked_sub(1).map(|j| &lines[j]).unwrap_or(&empty);
-        let lower_line = lines.get(i + 1).unwrap_or(&empty);
+const RESET: &str = "\x1b[0m";
+const PART_COLOR: &str = "\x1b[32m";
+const LOOSE_COLOR: &str = "\x1b[31m";
+const SYMBOL_COLOR: &str = "\x1b[36m";
+const GEAR_COLOR: &str = "\x1b[1;33m";
 
-        while let Some((start, end)) = first_integer_boundaries(line, offset) {
-            offset = end + 1;
-            let left = start.saturating_sub(1);
-            let right = (end + 1).min(line.len() - 1);
+pub fn is_default_symbol(c: char) -> bool {
+    !c.is_numeric() && c != '.'
+}
 
-            let is_part_number = [line[left], line[right]]
-                .iter()
-                .chain(higher_line.get(left..=right).into_iter().flatten())
-                .chain(lower_line.get(left..=right).into_iter().flatten())
-                .any(|&c| !c.is_numeric() && c != '.');
+pub struct Schematic {
+    lines: Vec<Vec<char>>,
+    numbers: Vec<Number>,
+    symbols: Vec<Symbol>,
+    // index of the number covering each cell
+    cells: Vec<Vec<Option<usize>>>,
+    // both sides of the bipartite adjacency graph
+    symbol_numbers: Vec<Vec<usize>>,
+    number_symbols: Vec<Vec<usize>>,
+}
 
-            if is_part_number {
-                sum += parse_integer(&line[start..=end]);
+impl Schematic {
+    pub fn new(lines: Vec<Vec<char>>, is_symbol: impl Fn(char) -> bool) -> Self {
+        let mut numbers = vec![];
+        let mut cells = lines
+            .iter()
+            .map(|line| vec![None; line.len()])
+            .collect_vec();
+
+        for (y, line) in lines.iter().enumerate() {
+            let mut offset = 0;
+            while let Some((start, end)) = first_integer_boundaries(line, offset) {
+                offset = end + 1;
+                cells[y][start..=end].fill(Some(numbers.len()));
+                numbers.push(Number {
+                    value: parse_integer(&line[start..=end]),
+                    y,
+                    start,
+                    end,
+                });
             }
         }
+
+        let symbols = lines
+            .iter()
+            .enumerate()
+            .flat_map(|(y, line)| {
+                line.iter()
+                    .enumerate()
+                    .filter(|&(_, &c)| !c.is_numeric() && is_symbol(c))
+                    .map(move |(x, &char)| Symbol { char, x, y })
+            })
+            .collect_vec();
+
+        let symbol_numbers = symbols
+            .iter()
+            .map(|&Symbol { x, y, .. }| {
+                (y.saturating_sub(1)..=y + 1)
+                    .cartesian_product(x.saturating_sub(1)..=x + 1)
+                    .flat_map(|(y, x)| *cells.get(y)?.get(x)?)
+                    .sorted_unstable()
+                    .dedup()
+                    .collect_vec()
+            })
+            .collect_vec();
+
+        let mut number_symbols = vec![vec![]; numbers.len()];
+        for (s, adjacent) in symbol_numbers.iter().enumerate() {
+            adjacent.iter().for_each(|&n| number_symbols[n].push(s));
+        }
+
+        Self {
+            lines,
+            numbers,
+            symbols,
+            cells,
+            symbol_numbers,
+            number_symbols,
+        }
     }
-    sum
-}
 
-fn integer_around(line: &[char], pos: usize) -> Option<u64> {
-    if !line[pos].is_numeric() {
-        None
-    } else {
-        let left = (0..=pos)
-            .rev()
-            .take_while(|&i| line[i].is_numeric())
-            .last()
-            .unwrap();
-        let right = (pos..line.len())
-            .take_while(|&i| line[i].is_numeric())
-            .last()
-            .unwrap();
+    pub fn from_file(filename: &str, is_symbol: impl Fn(char) -> bool) -> Self {
+        let lines = crate::utils::read_lines(filename)
+            .map(|line| line.chars().collect())
+            .collect();
+        Self::new(lines, is_symbol)
+    }
 
-        Some(parse_integer(&line[left..=right]))
+    pub fn numbers(&self) -> &[Number] {
+        &self.numbers
+    }
+
+    pub fn symbols(&self) -> &[Symbol] {
+        &self.symbols
+    }
+
+    pub fn number_at(&self, x: usize, y: usize) -> Option<&Number> {
+        let n = (*self.cells.get(y)?.get(x)?)?;
+        Some(&self.numbers[n])
+    }
+
+    pub fn adjacent_numbers(&self, symbol: usize) -> impl Iterator<Item = &Number> {
+        self.symbol_numbers[symbol]
+            .iter()
+            .map(|&n| &self.numbers[n])
+    }
+
+    pub fn adjacent_symbols(&self, number: usize) -> impl Iterator<Item = &Symbol> {
+        self.number_symbols[number]
+            .iter()
+            .map(|&s| &self.symbols[s])
+    }
+
+    // Numbers next to at least one symbol `c`, in reading order.
+    pub fn numbers_adjacent_to(&self, c: char) -> Vec<&Number> {
+        (0..self.numbers.len())
+            .filter(|&n| self.adjacent_symbols(n).any(|symbol| symbol.char == c))
+            .map(|n| &self.numbers[n])
+            .collect()
+    }
+
+    pub fn part_numbers(&self) -> impl Iterator<Item = &Number> {
+        self.numbers
+            .iter()
+            .zip(&self.number_symbols)
+            .filter(|(_, symbols)| !symbols.is_empty())
+            .map(|(number, _)| number)
+    }
+
+    pub fn loose_numbers(&self) -> impl Iterator<Item = &Number> {
+        self.numbers
+            .iter()
+            .zip(&self.number_symbols)
+            .filter(|(_, symbols)| symbols.is_empty())
+            .map(|(number, _)| number)
     }
-}
 
-fn integers_around(line: &[char], pos: usize) -> Vec<u64> {
-    if let Some(middle) = integer_around(line, pos) {
-        vec![middle]
-    } else {
-        let mut res = vec![];
-        if let Some(right) = (pos > 0).then(|| integer_around(line, pos - 1)).flatten() {
-            res.push(right);
+    // Symbols `c` with exactly `parts` adjacent numbers.
+    pub fn gears(&self, c: char, parts: usize) -> impl Iterator<Item = (&Symbol, Vec<&Number>)> {
+        self.symbols
+            .iter()
+            .enumerate()
+            .filter(move |&(s, symbol)| symbol.char == c && self.symbol_numbers[s].len() == parts)
+            .map(|(s, symbol)| (symbol, self.adjacent_numbers(s).collect()))
+    }
+
+    // Colours part numbers, loose numbers and symbols, highlights symbols `gear` with
+    // exactly `parts` numbers and annotates their lines with the ratios.
+    pub fn render(&self, gear: char, parts: usize) -> String {
+        let gears = self.gears(gear, parts).collect_vec();
+        let mut out = String::new();
+
+        // colour of each symbol cell, gears drawn over plain symbols
+        let mut symbol_colors = self
+            .cells
+            .iter()
+            .map(|row| vec![None; row.len()])
+            .collect_vec();
+        for symbol in &self.symbols {
+            symbol_colors[symbol.y][symbol.x] = Some(SYMBOL_COLOR);
         }
-        if let Some(left) = (pos + 1 < line.len())
-            .then(|| integer_around(line, pos + 1))
-            .flatten()
-        {
-            res.push(left);
+        for (symbol, _) in &gears {
+            symbol_colors[symbol.y][symbol.x] = Some(GEAR_COLOR);
         }
-        res
-    }
-}
 
-pub fn gear_ratio(filename: &str) -> u64 {
-    let lines: Vec<Vec<_>> = crate::utils::read_lines(filename)
-        .map(|line| line.chars().collect())
-        .collect();
-
-    let mut sum = 0;
-    for (y, line) in lines.iter().enumerate() {
-        for (x, &c) in line.iter().enumerate() {
-            if c != '*' {
-                continue;
-            }
-            let mut numbers = integers_around(line, x);
-            if y > 0 {
-                numbers.extend(integers_around(&lines[y - 1], x));
-            }
-            if y + 1 < lines.len() {
-                numbers.extend(integers_around(&lines[y + 1], x));
+        for (y, line) in self.lines.iter().enumerate() {
+            let style = |x: usize| match self.cells[y][x] {
+                Some(n) if self.number_symbols[n].is_empty() => Some(LOOSE_COLOR),
+                Some(_) => Some(PART_COLOR),
+                None => symbol_colors[y][x],
+            };
+
+            for (style, chars) in &line.iter().enumerate().group_by(|&(x, _)| style(x)) {
+                let chars = chars.map(|(_, c)| c).collect::<String>();
+                match style {
+                    Some(color) => out.push_str(&format!("{color}{chars}{RESET}")),
+                    None => out.push_str(&chars),
+                }
             }
-            if numbers.len() == 2 {
-                sum += numbers[0] * numbers[1];
+
+            let annotations = gears
+                .iter()
+                .filter(|(symbol, _)| symbol.y == y)
+                .map(|(symbol, numbers)| {
+                    let ratio = numbers.iter().map(|n| n.value).product::<u64>();
+                    let factors = numbers.iter().map(|n| n.value).join(" * ");
+                    format!(
+                        "{GEAR_COLOR}{}{RESET} at {}: {factors} = {ratio}",
+                        symbol.char, symbol.x
+                    )
+                })
+                .join(", ");
+            if !annotations.is_empty() {
+                out.push_str("   ");
+                out.push_str(&annotations);
             }
+            out.push('\n');
         }
+
+        out
     }
+}
 
-    sum
+pub fn print_schematic(filename: &str) {
+    print!(
+        "{}",
+        Schematic::from_file(filename, is_default_symbol).render('*', 2)
+    );
+}
+
+pub fn sum_part_numbers(filename: &str) -> u64 {
+    Schematic::from_file(filename, is_default_symbol)
+        .part_numbers()
+        .map(|number| number.value)
+        .sum()
+}
+
+pub fn gear_ratio(filename: &str) -> u64 {
+    Schematic::from_file(filename, is_default_symbol)
+        .gears('*', 2)
+        .map(|(_, numbers)| numbers.iter().map(|number| number.value).product::<u64>())
+        .sum()
 }
 
 pub fn solution() -> Day<u64, u64> {
@@ -163,21 +310,66 @@ mod d03_tests {
     }
 
     #[test]
-    fn integer_around_test() {
-        let filename = solution().part_1.examples[0];
+    fn number_at_test() {
+        let schematic = Schematic::from_file(solution().part_1.examples[0], is_default_symbol);
 
-        let lines: Vec<Vec<_>> = crate::utils::read_lines(filename)
-            .map(|line| line.chars().collect())
-            .collect();
-
-        let number = integer_around(&lines[0], 0);
+        let number = schematic.number_at(0, 0).map(|n| n.value);
         assert_eq!(number, Some(467));
 
-        let number = integer_around(&lines[0], 2);
+        let number = schematic.number_at(2, 0).map(|n| n.value);
         assert_eq!(number, Some(467));
 
-        let number = integer_around(&lines[2], 3);
+        let number = schematic.number_at(3, 2).map(|n| n.value);
         assert_eq!(number, Some(35));
+
+        let number = schematic.number_at(3, 0).map(|n| n.value);
+        assert_eq!(number, None);
+    }
+
+    #[test]
+    fn schematic_test() {
+        let values = |numbers: Vec<&Number>| numbers.iter().map(|n| n.value).collect_vec();
+        let schematic = Schematic::from_file(solution().part_1.examples[0], is_default_symbol);
+
+        assert_eq!(
+            vec![467, 35, 617, 755, 598],
+            values(schematic.numbers_adjacent_to('*'))
+        );
+        assert_eq!(vec![114, 58], values(schematic.loose_numbers().collect()));
+        assert_eq!(
+            vec![vec![467, 35], vec![755, 598]],
+            schematic
+                .gears('*', 2)
+                .map(|(_, numbers)| values(numbers))
+                .collect_vec()
+        );
+        assert_eq!(
+            vec![(3, 4)],
+            schematic
+                .gears('*', 1)
+                .map(|(symbol, _)| (symbol.x, symbol.y))
+                .collect_vec()
+        );
+
+        let stars = Schematic::from_file(solution().part_1.examples[0], |c| c == '*');
+        assert_eq!(3, stars.symbols().len());
+        assert_eq!(2472, stars.part_numbers().map(|n| n.value).sum::<u64>());
+        assert_eq!(vec![633], values(schematic.numbers_adjacent_to('#')));
+    }
+
+    #[test]
+    fn render_test() {
+        let schematic = Schematic::from_file(solution().part_1.examples[0], is_default_symbol);
+        let rendered = schematic.render('*', 2);
+        let lines = rendered.lines().collect_vec();
+
+        assert_eq!(10, lines.len());
+        assert_eq!("\x1b[32m467\x1b[0m..\x1b[31m114\x1b[0m..", lines[0]);
+        assert_eq!(
+            "...\x1b[1;33m*\x1b[0m......   \x1b[1;33m*\x1b[0m at 3: 467 * 35 = 16345",
+            lines[1]
+        );
+        assert_eq!("\x1b[32m617\x1b[0m\x1b[36m*\x1b[0m......", lines[4]);
     }
 
     #[test]
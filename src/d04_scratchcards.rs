@@ -1,6 +1,10 @@
+use std::ops::Range;
+
+use itertools::{EitherOrBoth, Itertools};
+
 use crate::utils::{Day, Task};
 
-fn parse_card(line: &str) -> (usize, Vec<u32>, Vec<u32>) {
+pub fn parse_card(line: &str) -> (usize, Vec<u32>, Vec<u32>) {
     let (id, all_numbers) = line.split_once(':').unwrap();
     let id = id
         .split_whitespace()
@@ -19,7 +23,7 @@ fn parse_card(line: &str) -> (usize, Vec<u32>, Vec<u32>) {
     (id, winning, actual)
 }
 
-fn points(mut winning: Vec<u32>, mut actual: Vec<u32>) -> u32 {
+pub fn points(mut winning: Vec<u32>, mut actual: Vec<u32>) -> u32 {
     winning.sort();
     actual.sort();
 
@@ -40,31 +44,243 @@ fn points(mut winning: Vec<u32>, mut actual: Vec<u32>) -> u32 {
     total
 }
 
+#[derive(Debug, Clone, Eq, PartialEq)]
+enum Numbers {
+    // bit `n` is set for number `n`
+    Mask(u128),
+    // sorted and deduplicated, for cards with numbers of 128 and above
+    Sorted(Vec<u32>),
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Card {
+    pub id: usize,
+    winning: Numbers,
+    actual: Numbers,
+}
+
+impl Card {
+    pub fn new(id: usize, mut winning: Vec<u32>, mut actual: Vec<u32>) -> Self {
+        let fits = winning.iter().chain(&actual).all(|&n| n < u128::BITS);
+        let (winning, actual) = match fits {
+            true => {
+                let mask = |numbers: Vec<u32>| numbers.iter().fold(0, |mask, n| mask | 1 << n);
+                (Numbers::Mask(mask(winning)), Numbers::Mask(mask(actual)))
+            }
+            false => {
+                for numbers in [&mut winning, &mut actual] {
+                    numbers.sort_unstable();
+                    numbers.dedup();
+                }
+                (Numbers::Sorted(winning), Numbers::Sorted(actual))
+            }
+        };
+
+        Self {
+            id,
+            winning,
+            actual,
+        }
+    }
+
+    pub fn from_line(line: &str) -> Self {
+        let (id, winning, actual) = parse_card(line);
+        Self::new(id, winning, actual)
+    }
+
+    pub fn matches(&self) -> usize {
+        match (&self.winning, &self.actual) {
+            (Numbers::Mask(winning), Numbers::Mask(actual)) => {
+                (winning & actual).count_ones() as usize
+            }
+            (Numbers::Sorted(winning), Numbers::Sorted(actual)) => winning
+                .iter()
+                .merge_join_by(actual, Ord::cmp)
+                .filter(EitherOrBoth::is_both)
+                .count(),
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn points(&self) -> u64 {
+        match self.matches() {
+            0 => 0,
+            matches => 2u64.pow(matches as u32 - 1),
+        }
+    }
+}
+
 pub fn total_points(filename: &str) -> u64 {
     crate::utils::read_lines(filename)
-        .map(|line| parse_card(&line))
-        .map(|(_, winning, actual)| points(winning, actual))
-        .filter(|&total| total > 0)
-        .map(|total| 2u64.pow(total - 1))
+        .map(|line| Card::from_line(&line).points())
         .sum()
 }
 
-pub fn total_scratchcards(filename: &str) -> usize {
-    let mut counts: Vec<_> = crate::utils::read_lines(filename)
-        .map(|line| parse_card(&line))
-        .map(|(_, winning, actual)| (1, points(winning, actual)))
-        .collect();
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum CopyRule {
+    // one copy of each of the next `matches` cards
+    Next,
+    // `factor` copies of each of the next `matches` cards
+    Scaled(usize),
+    // `matches` copies of each of the next `matches` cards
+    PerMatch,
+    // like `Next`, but running past the last card continues from the first one;
+    // copies landing on already scratched cards are counted but win nothing
+    Wrapping,
+}
+
+impl CopyRule {
+    // Target ranges with the copies each target gets per instance of the card.
+    fn targets(&self, card: usize, matches: usize, cards: usize) -> [(Range<usize>, usize); 2] {
+        let end = (card + 1 + matches).min(cards);
+        match self {
+            CopyRule::Next => [(card + 1..end, 1), (0..0, 0)],
+            CopyRule::Scaled(factor) => [(card + 1..end, *factor), (0..0, 0)],
+            CopyRule::PerMatch => [(card + 1..end, matches), (0..0, 0)],
+            CopyRule::Wrapping => {
+                let wrapped = (card + 1 + matches.min(cards - 1)).saturating_sub(cards);
+                [(card + 1..end, 1), (0..wrapped, 1)]
+            }
+        }
+    }
+}
+
+pub struct Cascade {
+    matches: Vec<usize>,
+    rule: CopyRule,
+}
 
-    for i in 0..counts.len() {
-        let (count, points) = counts[i];
+impl Cascade {
+    pub fn new(matches: Vec<usize>, rule: CopyRule) -> Self {
+        Self { matches, rule }
+    }
+
+    pub fn from_file(filename: &str, rule: CopyRule) -> Self {
+        let matches = crate::utils::read_lines(filename)
+            .map(|line| Card::from_line(&line).matches())
+            .collect();
+        Self::new(matches, rule)
+    }
+
+    // Instances of every card, linear in the number of cards.
+    pub fn counts(&self) -> Vec<usize> {
+        let n = self.matches.len();
+        // pending range additions, `late` ones land on already scratched cards
+        let mut diff = vec![0isize; n + 1];
+        let mut late = vec![0isize; n + 1];
+        let mut counts = Vec::with_capacity(n);
+        let mut running = 0;
+
+        for (card, &matches) in self.matches.iter().enumerate() {
+            running += diff[card];
+            let count = 1 + running as usize;
+            counts.push(count);
+
+            for (range, per) in self.rule.targets(card, matches, n) {
+                let diff = match range.start > card {
+                    true => &mut diff,
+                    false => &mut late,
+                };
+                diff[range.start] += (count * per) as isize;
+                diff[range.end] -= (count * per) as isize;
+            }
+        }
+
+        let mut running = 0;
+        for (count, late) in counts.iter_mut().zip(late) {
+            running += late;
+            *count += running as usize;
+        }
         counts
-            .iter_mut()
-            .skip(i + 1)
-            .take(points as usize)
-            .for_each(|c| c.0 += count);
     }
 
-    counts.into_iter().map(|(count, _)| count).sum()
+    pub fn total(&self) -> usize {
+        self.counts().into_iter().sum()
+    }
+
+    // For every card, the earlier scratched cards its copies came from and how many.
+    pub fn provenance(&self) -> Vec<Vec<(usize, usize)>> {
+        let n = self.matches.len();
+        let mut sources = vec![vec![]; n];
+        let mut winning = vec![1; n];
+
+        for (card, &matches) in self.matches.iter().enumerate() {
+            let count = winning[card];
+            for (range, per) in self.rule.targets(card, matches, n) {
+                for target in range {
+                    sources[target].push((card, count * per));
+                    if target > card {
+                        winning[target] += count * per;
+                    }
+                }
+            }
+        }
+
+        sources
+    }
+
+    // Copies won starting from a single original `card`, down to `depth` levels, `None` for
+    // an unknown card.
+    pub fn tree(&self, card: usize, depth: Option<usize>) -> Option<String> {
+        fn grow(
+            cascade: &Cascade,
+            card: usize,
+            copies: usize,
+            level: usize,
+            depth: Option<usize>,
+            out: &mut String,
+        ) {
+            out.push_str(&format!(
+                "{}Card {} x{copies}\n",
+                "  ".repeat(level),
+                card + 1
+            ));
+            if depth.is_some_and(|depth| level >= depth) {
+                return;
+            }
+
+            let n = cascade.matches.len();
+            for (range, per) in cascade.rule.targets(card, cascade.matches[card], n) {
+                for target in range {
+                    match target > card {
+                        true => grow(cascade, target, copies * per, level + 1, depth, out),
+                        false => out.push_str(&format!(
+                            "{}Card {} x{}\n",
+                            "  ".repeat(level + 1),
+                            target + 1,
+                            copies * per
+                        )),
+                    }
+                }
+            }
+        }
+
+        if card >= self.matches.len() {
+            return None;
+        }
+        let mut out = String::new();
+        grow(self, card, 1, 0, depth, &mut out);
+        Some(out)
+    }
+}
+
+// `number` counts cards from 1 as in the input.
+pub fn print_tree(filename: &str, number: usize, depth: Option<usize>) {
+    let cascade = Cascade::from_file(filename, CopyRule::Next);
+    match number
+        .checked_sub(1)
+        .and_then(|card| cascade.tree(card, depth))
+    {
+        Some(tree) => print!("{tree}"),
+        None => eprintln!(
+            "usage: 4 tree [card in 1..={}] [depth]",
+            cascade.matches.len()
+        ),
+    }
+}
+
+pub fn total_scratchcards(filename: &str) -> usize {
+    Cascade::from_file(filename, CopyRule::Next).total()
 }
 
 pub fn solution() -> Day<u64, usize> {
@@ -103,6 +319,30 @@ mod d04_tests {
         assert_eq!(card3.2, vec![69, 82, 63, 72, 16, 21, 14, 1]);
     }
 
+    #[test]
+    fn card_test() {
+        let lines: Vec<_> = crate::utils::read_lines(solution().part_1.examples[0]).collect();
+
+        let card = Card::from_line(&lines[0]);
+        assert!(matches!(card.winning, Numbers::Mask(_)));
+        assert_eq!(1, card.id);
+        assert_eq!(4, card.matches());
+        assert_eq!(8, card.points());
+
+        for line in &lines {
+            let (_, winning, actual) = parse_card(line);
+            assert_eq!(
+                points(winning, actual) as usize,
+                Card::from_line(line).matches()
+            );
+        }
+
+        let card = Card::new(2, vec![500, 3, 1000, 127], vec![1000, 7, 127, 500, 500]);
+        assert!(matches!(card.actual, Numbers::Sorted(_)));
+        assert_eq!(3, card.matches());
+        assert_eq!(0, Card::new(3, vec![128], vec![127]).points());
+    }
+
     #[test]
     fn p1_example_test() {
         let res = solution().part_1.run_example(0);
@@ -114,4 +354,46 @@ mod d04_tests {
         let res = solution().part_2.run_example(0);
         assert_eq!(res, 30);
     }
+
+    #[test]
+    fn cascade_test() {
+        let filename = solution().part_2.examples[0];
+
+        let cascade = Cascade::from_file(filename, CopyRule::Next);
+        assert_eq!(vec![1, 2, 4, 8, 14, 1], cascade.counts());
+        assert_eq!(vec![(0, 1), (1, 2), (2, 4)], cascade.provenance()[3]);
+        assert!(cascade.provenance()[0].is_empty());
+
+        assert_eq!(
+            116,
+            Cascade::from_file(filename, CopyRule::Scaled(2)).total()
+        );
+        assert_eq!(
+            147,
+            Cascade::from_file(filename, CopyRule::PerMatch).total()
+        );
+        assert_eq!(30, Cascade::from_file(filename, CopyRule::Wrapping).total());
+
+        let wrapping = Cascade::new(vec![0, 2, 1], CopyRule::Wrapping);
+        assert_eq!(vec![4, 1, 2], wrapping.counts());
+        assert_eq!(vec![(1, 1), (2, 2)], wrapping.provenance()[0]);
+    }
+
+    #[test]
+    fn tree_test() {
+        let cascade = Cascade::from_file(solution().part_2.examples[0], CopyRule::Next);
+
+        assert_eq!(
+            "Card 1 x1\n  Card 2 x1\n  Card 3 x1\n  Card 4 x1\n  Card 5 x1\n",
+            cascade.tree(0, Some(1)).unwrap()
+        );
+        assert_eq!(15, cascade.tree(0, None).unwrap().lines().count());
+        assert_eq!(None, cascade.tree(6, None));
+        assert_eq!(
+            "Card 2 x1\n  Card 3 x1\n    Card 1 x1\n  Card 1 x1\n",
+            Cascade::new(vec![0, 2, 1], CopyRule::Wrapping)
+                .tree(1, None)
+                .unwrap()
+        );
+    }
 }
@@ -1,4 +1,6 @@
-use std::{cmp::Ordering, str::FromStr};
+use std::{cmp::Ordering, ops::Range, str::FromStr};
+
+use itertools::Itertools;
 
 use crate::utils::{Day, Task};
 
@@ -38,73 +40,95 @@ impl Mapping {
         })
     }
 
-    fn map_value(&self, val: u64) -> u64 {
+    pub fn map_value(&self, val: u64) -> u64 {
         self.binary_search_range(val)
             .map_or(val, |i| self.ranges[i].map_value_unchecked(val))
     }
 
-    pub fn collapse_mappings(&self, other: &Self) -> Mapping {
-        let mut ranges = vec![];
+    fn pieces(&self) -> Vec<MappingRange> {
+        let mut pieces = vec![];
+        let mut start = 0;
 
         for range in &self.ranges {
-            let MappingRange {
-                mut src,
-                mut dst,
-                mut len,
-            } = *range;
-            let mut left = other.binary_search_range(dst);
-            let right = other.binary_search_range(dst + len - 1);
-
-            loop {
-                if left == right {
-                    if left.is_ok() {
-                        let containing_range = other.ranges[left.unwrap()];
-                        let offset = dst - containing_range.src;
-                        ranges.push(MappingRange::new(containing_range.dst + offset, src, len));
-                    } else {
-                        ranges.push(MappingRange::new(dst, src, len));
-                    }
-                    break;
-                }
-
-                match left {
-                    Ok(i) => {
-                        let containing_range = other.ranges[i];
-                        let offset = dst - containing_range.src;
-
-                        let new_len = containing_range.len - offset;
-                        ranges.push(MappingRange::new(
-                            containing_range.dst + offset,
-                            src,
-                            new_len,
-                        ));
-                        dst += new_len;
-                        src += new_len;
-                        len -= new_len;
-                        left = if i + 1 < other.ranges.len() && other.ranges[i + 1].src == dst {
-                            Ok(i + 1)
-                        } else {
-                            Err(i)
-                        }
-                    }
-                    Err(i) => {
-                        let next_range = other.ranges[i];
-                        let new_len = next_range.src - dst;
-                        ranges.push(MappingRange::new(dst, src, new_len));
-                        dst += new_len;
-                        src += new_len;
-                        len -= new_len;
-                        left = Ok(i);
-                    }
-                }
+            if start < range.src {
+                pieces.push(MappingRange::new(start, start, range.src - start));
             }
+            pieces.push(*range);
+            start = range.src + range.len;
         }
+        pieces.push(MappingRange::new(start, start, u64::MAX - start));
 
-        ranges.sort();
+        pieces
+    }
+
+    pub fn collapse_mappings(&self, other: &Self) -> Mapping {
+        let other_pieces = other.pieces();
+
+        let ranges = self
+            .pieces()
+            .into_iter()
+            .flat_map(|piece| {
+                let end = piece.dst + piece.len;
+                other_pieces
+                    .iter()
+                    .filter(move |p| p.src < end && piece.dst < p.src + p.len)
+                    .map(move |p| {
+                        let start = p.src.max(piece.dst);
+                        let len = (p.src + p.len).min(end) - start;
+                        MappingRange::new(
+                            p.map_value_unchecked(start),
+                            piece.src + start - piece.dst,
+                            len,
+                        )
+                    })
+            })
+            .filter(|range| range.src != range.dst)
+            .collect();
 
         Mapping { ranges }
     }
 
+    pub fn compose<'a>(mappings: impl IntoIterator<Item = &'a Mapping>) -> Mapping {
+        mappings
+            .into_iter()
+            .fold(Mapping { ranges: vec![] }, |acc, m| {
+                acc.collapse_mappings(m)
+            })
+    }
+
+    pub fn preimage(&self, range: Range<u64>) -> Vec<Range<u64>> {
+        let ranges = self
+            .pieces()
+            .into_iter()
+            .filter(|p| p.dst < range.end && range.start < p.dst + p.len)
+            .map(|p| {
+                let start = p.dst.max(range.start);
+                let end = (p.dst + p.len).min(range.end);
+                (p.src + start - p.dst)..(p.src + end - p.dst)
+            })
+            .collect();
+
+        merge_ranges(ranges)
+    }
+
+    pub fn inverse(&self) -> Option<Mapping> {
+        let sources = merge_ranges(self.ranges.iter().map(|r| r.src..r.src + r.len).collect());
+        let destinations = merge_ranges(self.ranges.iter().map(|r| r.dst..r.dst + r.len).collect());
+
+        if sources != destinations {
+            return None;
+        }
+
+        let mut ranges = self
+            .ranges
+            .iter()
+            .map(|r| MappingRange::new(r.src, r.dst, r.len))
+            .collect_vec();
+        ranges.sort();
+
+        Some(Mapping { ranges })
+    }
+
     /*
        1..................................100
           10....20  30......50      80..........120
@@ -143,56 +167,167 @@ impl FromStr for MappingRange {
     }
 }
 
-fn parse_file(filename: &str) -> (Vec<u64>, Vec<Mapping>) {
-    let mut lines = crate::utils::read_lines(filename);
-    let seeds = lines
-        .next()
-        .map(|line| parse_numbers(line.split_once(':').unwrap().1))
-        .unwrap();
+fn merge_ranges(mut ranges: Vec<Range<u64>>) -> Vec<Range<u64>> {
+    ranges.retain(|r| !r.is_empty());
+    ranges.sort_by_key(|r| r.start);
+    ranges.into_iter().fold(vec![], |mut merged, r| {
+        match merged.last_mut() {
+            Some(last) if r.start <= last.end => last.end = last.end.max(r.end),
+            _ => merged.push(r),
+        }
+        merged
+    })
+}
+
+fn chain_preimage(mappings: &[&Mapping], range: Range<u64>) -> Vec<Range<u64>> {
+    mappings.iter().rev().fold(vec![range], |ranges, m| {
+        merge_ranges(ranges.into_iter().flat_map(|r| m.preimage(r)).collect())
+    })
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum PathError {
+    Missing,
+    Ambiguous,
+}
+
+struct CategoryMapping {
+    src: String,
+    dst: String,
+    mapping: Mapping,
+}
+
+pub struct Almanac {
+    seeds: Vec<u64>,
+    mappings: Vec<CategoryMapping>,
+}
+
+impl Almanac {
+    pub fn from_file(filename: &str) -> Self {
+        let mut lines = crate::utils::read_lines(filename);
+        let seeds = lines
+            .next()
+            .map(|line| parse_numbers(line.split_once(':').unwrap().1))
+            .unwrap();
+
+        let mut mappings = vec![];
+
+        for line in lines {
+            if let Some(header) = line.strip_suffix(" map:") {
+                let (src, dst) = header.split_once("-to-").unwrap();
+                mappings.push(CategoryMapping {
+                    src: src.to_string(),
+                    dst: dst.to_string(),
+                    mapping: Mapping { ranges: vec![] },
+                });
+            } else if !line.is_empty() {
+                let ranges = &mut mappings.last_mut().unwrap().mapping.ranges;
+                ranges.push(line.parse().unwrap());
+            }
+        }
+
+        mappings.iter_mut().for_each(|m| m.mapping.ranges.sort());
 
-    lines.next();
+        Self { seeds, mappings }
+    }
 
-    let mut tmp = vec![];
-    let mut mappings = vec![];
+    pub fn seeds(&self) -> &[u64] {
+        &self.seeds
+    }
 
-    for line in lines {
-        if line.is_empty() {
-            tmp.sort();
-            mappings.push(Mapping { ranges: tmp });
-            tmp = vec![];
-            continue;
+    pub fn categories(&self) -> Vec<&str> {
+        self.mappings
+            .iter()
+            .flat_map(|m| [m.src.as_str(), m.dst.as_str()])
+            .unique()
+            .collect()
+    }
+
+    pub fn mapping(&self, src: &str, dst: &str) -> Option<&Mapping> {
+        self.mappings
+            .iter()
+            .find(|m| m.src == src && m.dst == dst)
+            .map(|m| &m.mapping)
+    }
+
+    fn find_paths<'a: 'b, 'b>(
+        &'a self,
+        from: &'b str,
+        to: &str,
+        visited: &mut Vec<&'b str>,
+        path: &mut Vec<&'a Mapping>,
+        found: &mut Vec<Vec<&'a Mapping>>,
+    ) {
+        if found.len() > 1 {
+            return;
+        }
+        if from == to {
+            found.push(path.clone());
+            return;
         }
-        if line.ends_with(':') {
-            continue;
+
+        visited.push(from);
+        for m in self.mappings.iter().filter(|m| m.src == from) {
+            if !visited.contains(&m.dst.as_str()) {
+                path.push(&m.mapping);
+                self.find_paths(&m.dst, to, visited, path, found);
+                path.pop();
+            }
         }
-        tmp.push(line.parse().unwrap());
+        visited.pop();
     }
 
-    tmp.sort();
-    mappings.push(Mapping { ranges: tmp });
+    pub fn path(&self, from: &str, to: &str) -> Result<Vec<&Mapping>, PathError> {
+        let mut found = vec![];
+        self.find_paths(from, to, &mut vec![], &mut vec![], &mut found);
 
-    (seeds, mappings)
+        match found.len() {
+            0 => Err(PathError::Missing),
+            1 => Ok(found.pop().unwrap()),
+            _ => Err(PathError::Ambiguous),
+        }
+    }
+
+    pub fn mapping_between(&self, from: &str, to: &str) -> Result<Mapping, PathError> {
+        Ok(Mapping::compose(self.path(from, to)?))
+    }
+
+    pub fn preimage(
+        &self,
+        from: &str,
+        to: &str,
+        range: Range<u64>,
+    ) -> Result<Vec<Range<u64>>, PathError> {
+        Ok(chain_preimage(&self.path(from, to)?, range))
+    }
+
+    pub fn seeds_for_locations(&self, locations: Range<u64>) -> Result<Vec<Range<u64>>, PathError> {
+        self.preimage("seed", "location", locations)
+    }
 }
 
 pub fn lowest_location(filename: &str) -> u64 {
-    let (seeds, mappings) = parse_file(filename);
-    seeds
-        .into_iter()
-        .map(|seed| mappings.iter().fold(seed, |val, m| m.map_value(val)))
+    let almanac = Almanac::from_file(filename);
+    let mapping = almanac.mapping_between("seed", "location").unwrap();
+    almanac
+        .seeds()
+        .iter()
+        .map(|&seed| mapping.map_value(seed))
         .min()
         .unwrap()
 }
 
 pub fn lowest_location_range(filename: &str) -> u64 {
-    let (seeds, mappings) = parse_file(filename);
+    let almanac = Almanac::from_file(filename);
+    let mapping = almanac.mapping_between("seed", "location").unwrap();
     let mut min = u64::MAX;
 
-    for seed_range in seeds.chunks(2) {
+    for seed_range in almanac.seeds().chunks(2) {
         let start = seed_range[0];
         let length = seed_range[1];
 
         let m = (start..(start + length))
-            .map(|seed| mappings.iter().fold(seed, |val, m| m.map_value(val)))
+            .map(|seed| mapping.map_value(seed))
             .min()
             .unwrap();
         min = min.min(m);
@@ -222,18 +357,95 @@ mod d05_tests {
 
     #[test]
     fn map_value_test() {
-        let (_, mappings) = parse_file(solution().part_1.examples[0]);
+        let almanac = Almanac::from_file(solution().part_1.examples[0]);
+        let mapping = almanac.mapping("seed", "soil").unwrap();
 
-        let res = mappings[0].map_value(1);
+        let res = mapping.map_value(1);
         assert_eq!(res, 1);
 
-        let res = mappings[0].map_value(79);
+        let res = mapping.map_value(79);
         assert_eq!(res, 81);
 
-        let res = mappings[0].map_value(99);
+        let res = mapping.map_value(99);
         assert_eq!(res, 51);
     }
 
+    #[test]
+    fn compose_test() {
+        let almanac = Almanac::from_file(solution().part_1.examples[0]);
+        let mappings = almanac.path("seed", "location").unwrap();
+        let composed = Mapping::compose(mappings.iter().copied());
+
+        for seed in 0..200 {
+            let expected = mappings.iter().fold(seed, |val, m| m.map_value(val));
+            assert_eq!(composed.map_value(seed), expected);
+        }
+    }
+
+    #[test]
+    fn inverse_test() {
+        let almanac = Almanac::from_file(solution().part_1.examples[0]);
+
+        let inverse = almanac.mapping("seed", "soil").unwrap().inverse().unwrap();
+        assert_eq!(inverse.map_value(81), 79);
+        assert_eq!(inverse.map_value(51), 99);
+        assert_eq!(inverse.map_value(10), 10);
+
+        let lossy = Mapping {
+            ranges: vec![MappingRange::new(50, 98, 2)],
+        };
+        assert!(lossy.inverse().is_none());
+        assert_eq!(lossy.preimage(50..51), vec![50..51, 98..99]);
+    }
+
+    #[test]
+    fn seeds_for_locations_test() {
+        let almanac = Almanac::from_file(solution().part_1.examples[0]);
+        let mappings = almanac.path("seed", "location").unwrap();
+
+        let seeds = almanac.seeds_for_locations(46..47).unwrap();
+        assert!(seeds.iter().any(|r| r.contains(&82)));
+        for seed in seeds.into_iter().flatten() {
+            assert_eq!(mappings.iter().fold(seed, |val, m| m.map_value(val)), 46);
+        }
+    }
+
+    #[test]
+    fn category_path_test() {
+        let mut almanac = Almanac::from_file(solution().part_1.examples[0]);
+        assert_eq!(almanac.categories().len(), 8);
+
+        let soil_to_humidity = almanac.mapping_between("soil", "humidity").unwrap();
+        let path = almanac.path("soil", "humidity").unwrap();
+        assert_eq!(path.len(), 5);
+        for soil in 0..100 {
+            let expected = path.iter().fold(soil, |val, m| m.map_value(val));
+            assert_eq!(soil_to_humidity.map_value(soil), expected);
+        }
+
+        assert_eq!(almanac.path("seed", "seed").unwrap().len(), 0);
+        assert_eq!(
+            almanac.path("location", "seed").err(),
+            Some(PathError::Missing)
+        );
+        assert_eq!(almanac.path("seed", "moon").err(), Some(PathError::Missing));
+
+        almanac.mappings.push(CategoryMapping {
+            src: "seed".to_string(),
+            dst: "water".to_string(),
+            mapping: Mapping { ranges: vec![] },
+        });
+        assert_eq!(
+            almanac.path("seed", "location").err(),
+            Some(PathError::Ambiguous)
+        );
+        assert_eq!(almanac.path("water", "location").unwrap().len(), 4);
+        assert_eq!(
+            almanac.seeds_for_locations(46..47).err(),
+            Some(PathError::Ambiguous)
+        );
+    }
+
     #[test]
     fn p1_example_test() {
         let res = solution().part_1.run_example(0);
@@ -1,3 +1,7 @@
+use std::{cmp::Reverse, ops::RangeInclusive};
+
+use num::{integer::Roots, Integer};
+
 use crate::utils::{Day, Task};
 
 fn parse_numbers(s: String) -> Vec<i64> {
@@ -9,27 +13,38 @@ fn parse_numbers(s: String) -> Vec<i64> {
         .collect()
 }
 
-fn ways_to_win(time: i64, dist: i64) -> i64 {
-    match time * time - 4 * dist {
-        0 if time % 2 == 0 => 1,
-        0 if time % 2 != 0 => 0,
-        d if d < 0 => 0,
-        d => {
-            let d_sqrt = (d as f64).sqrt();
-
-            let t1 = (time as f64 - d_sqrt) / 2.0;
-            let t1 = t1.ceil() as i64;
-            let t2 = (time as f64 + d_sqrt) / 2.0;
-            let t2 = t2.floor() as i64;
-
-            let is_exact = t1 * t1 - time * t1 + dist == 0;
-            if is_exact {
-                t2 - t1 - 1
-            } else {
-                t2 - t1 + 1
-            }
-        }
+// Hold times beating `dist`, i.e. integer `hold` with `hold * (time - hold) > dist`.
+pub fn winning_holds<T>(time: T, dist: T) -> Option<RangeInclusive<T>>
+where
+    T: Clone + Integer + Roots + From<u8>,
+{
+    let wins = |hold: &T| hold.clone() * (time.clone() - hold.clone()) > dist;
+
+    let disc = time.clone() * time.clone() - T::from(4) * dist.clone();
+    if time < T::zero() || disc < T::zero() {
+        return None;
     }
+
+    // the smaller root lies in (lo - 1, lo + 1/2], so the first win is `lo` or `lo + 1`
+    let lo = (time.clone() - disc.sqrt()).div_floor(&T::from(2));
+    let lo = match wins(&lo) {
+        true => lo,
+        false => lo + T::one(),
+    }
+    .max(T::zero());
+    let hi = time.clone() - lo.clone();
+
+    (lo <= hi && wins(&lo)).then_some(lo..=hi)
+}
+
+pub fn ways_to_win<T>(time: T, dist: T) -> T
+where
+    T: Clone + Integer + Roots + From<u8>,
+{
+    winning_holds(time, dist).map_or(T::zero(), |holds| {
+        let (lo, hi) = holds.into_inner();
+        hi - lo + T::one()
+    })
 }
 
 fn parse_number(line: String) -> i64 {
@@ -43,15 +58,143 @@ fn parse_number(line: String) -> i64 {
     s.parse().unwrap()
 }
 
-fn product_of_ways_to_win(filename: &str) -> i64 {
+// First and last hold beating `dist`, for `distance` concave in the hold time.
+pub fn winning_holds_by(
+    time: i64,
+    dist: i64,
+    distance: impl Fn(i64) -> i64,
+) -> Option<RangeInclusive<i64>> {
+    // first hold in `lo..hi` satisfying a predicate that turns from false to true
+    fn partition_point(mut lo: i64, mut hi: i64, pred: impl Fn(i64) -> bool) -> i64 {
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            match pred(mid) {
+                true => hi = mid,
+                false => lo = mid + 1,
+            }
+        }
+        lo
+    }
+
+    if time < 0 {
+        return None;
+    }
+
+    let peak = partition_point(0, time, |hold| distance(hold + 1) <= distance(hold));
+    if distance(peak) <= dist {
+        return None;
+    }
+    let lo = partition_point(0, peak, |hold| distance(hold) > dist);
+    let hi = partition_point(peak, time + 1, |hold| distance(hold) <= dist) - 1;
+
+    Some(lo..=hi)
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Boat {
+    // speed gained per ms of holding the button
+    acceleration: i64,
+    max_speed: Option<i64>,
+}
+
+impl Default for Boat {
+    fn default() -> Self {
+        Self {
+            acceleration: 1,
+            max_speed: None,
+        }
+    }
+}
+
+impl Boat {
+    // Negative rates would make the distance convex in the hold time, so they are rejected.
+    pub fn new(acceleration: i64, max_speed: Option<i64>) -> Option<Self> {
+        (acceleration >= 0 && max_speed.is_none_or(|max| max >= 0)).then_some(Self {
+            acceleration,
+            max_speed,
+        })
+    }
+
+    pub fn distance(&self, hold: i64, time: i64) -> i64 {
+        let speed = self.acceleration * hold;
+        let speed = self.max_speed.map_or(speed, |max| speed.min(max));
+        speed * (time - hold)
+    }
+
+    pub fn winning_holds(&self, time: i64, dist: i64) -> Option<RangeInclusive<i64>> {
+        if self.acceleration == 0 || self.max_speed == Some(0) {
+            return winning_holds_by(time, dist, |hold| self.distance(hold, time));
+        }
+
+        // `acceleration * x > dist` is `x > dist / acceleration` rounded down
+        let holds = winning_holds(time, Integer::div_floor(&dist, &self.acceleration))?;
+        match self.max_speed {
+            None => Some(holds),
+            // at full speed the boat still has to cover `dist` in the remaining time
+            Some(max) => {
+                let (lo, hi) = holds.into_inner();
+                let hi = hi.min(time - Integer::div_floor(&dist, &max) - 1);
+                (lo <= hi).then_some(lo..=hi)
+            }
+        }
+    }
+
+    pub fn ways_to_win(&self, time: i64, dist: i64) -> i64 {
+        self.winning_holds(time, dist)
+            .map_or(0, |holds| holds.end() - holds.start() + 1)
+    }
+
+    pub fn races_product(&self, races: &[(i64, i64)]) -> i64 {
+        races
+            .iter()
+            .map(|&(time, dist)| self.ways_to_win(time, dist))
+            .product()
+    }
+
+    // Splits `budget` ms between races with the given records maximising the product
+    // of ways to win, returns the time given to every race and the product.
+    pub fn best_allocation(&self, budget: usize, dists: &[i64]) -> (Vec<i64>, i64) {
+        // best[i][b]: best product of the first `i` races within `b` ms and the time of race `i`
+        let mut best = vec![vec![(1, 0); budget + 1]];
+
+        for &dist in dists {
+            let prev = best.last().unwrap();
+            let row = (0..=budget)
+                .map(|b| {
+                    (0..=b)
+                        .map(|time| (prev[b - time].0 * self.ways_to_win(time as i64, dist), time))
+                        .max_by_key(|&(product, time)| (product, Reverse(time)))
+                        .unwrap()
+                })
+                .collect();
+            best.push(row);
+        }
+
+        let mut left = budget;
+        let mut times = vec![0; dists.len()];
+        for i in (1..=dists.len()).rev() {
+            let time = best[i][left].1;
+            times[i - 1] = time as i64;
+            left -= time;
+        }
+
+        (times, best[dists.len()][budget].0)
+    }
+}
+
+fn parse_races(filename: &str) -> Vec<(i64, i64)> {
     let mut lines = crate::utils::read_lines(filename);
     let times = parse_numbers(lines.next().unwrap());
     let dists = parse_numbers(lines.next().unwrap());
-    times
-        .into_iter()
-        .zip(dists)
-        .map(|(time, dist)| ways_to_win(time, dist))
-        .product()
+    times.into_iter().zip(dists).collect()
+}
+
+pub fn product_of_ways_to_win_with(filename: &str, boat: &Boat) -> i64 {
+    boat.races_product(&parse_races(filename))
+}
+
+fn product_of_ways_to_win(filename: &str) -> i64 {
+    product_of_ways_to_win_with(filename, &Boat::default())
 }
 
 fn ways_to_win_task(filename: &str) -> i64 {
@@ -76,6 +219,8 @@ pub fn solution() -> Day<i64, i64> {
 
 #[cfg(test)]
 mod d06_tests {
+    use num::BigInt;
+
     use super::*;
 
     #[test]
@@ -85,6 +230,117 @@ mod d06_tests {
         assert_eq!(9, ways_to_win(30, 200));
     }
 
+    fn brute_force(time: i64, dist: i64) -> Option<RangeInclusive<i64>> {
+        let wins = (0..=time)
+            .filter(|hold| hold * (time - hold) > dist)
+            .collect::<Vec<_>>();
+        Some(*wins.first()?..=*wins.last()?)
+    }
+
+    #[test]
+    fn winning_holds_test() {
+        assert_eq!(Some(2..=5), winning_holds(7, 9));
+        assert_eq!(Some(11..=19), winning_holds(30, 200));
+        assert_eq!(None, winning_holds(10, 25));
+        assert_eq!(Some(0..=4), winning_holds(4, -1));
+
+        for time in 0..60 {
+            for dist in -3..=time * time / 4 + 2 {
+                let expected = brute_force(time, dist);
+                assert_eq!(expected, winning_holds(time, dist), "{time} {dist}");
+
+                let wide = winning_holds(time as i128, dist as i128);
+                assert_eq!(expected, wide.map(|r| *r.start() as i64..=*r.end() as i64));
+
+                let big = winning_holds(BigInt::from(time), BigInt::from(dist));
+                let big = big.map(|r| {
+                    let (lo, hi) = r.into_inner();
+                    i64::try_from(lo).unwrap()..=i64::try_from(hi).unwrap()
+                });
+                assert_eq!(expected, big);
+            }
+        }
+    }
+
+    #[test]
+    fn big_race_test() {
+        let time = 10_000_000_000_000_000_000i128;
+        let half = time / 2;
+        assert_eq!(Some(half..=half), winning_holds(time, half * half - 1));
+        assert_eq!(3, ways_to_win(time, (half - 1) * (half + 1) - 1));
+
+        let time = BigInt::from(10).pow(40);
+        let half: BigInt = &time / 2;
+        let dist: BigInt = &half * &half - 1;
+        assert_eq!(Some(half.clone()..=half), winning_holds(time.clone(), dist));
+        assert_eq!(BigInt::from(0), ways_to_win(time.clone(), &time * &time));
+    }
+
+    #[test]
+    fn boat_test() {
+        let boat = Boat::new(2, None).unwrap();
+        assert_eq!(Some(1..=6), boat.winning_holds(7, 9));
+
+        let boat = Boat::new(1, Some(3)).unwrap();
+        assert_eq!(Some(2..=3), boat.winning_holds(7, 9));
+        assert_eq!(None, boat.winning_holds(7, 12));
+
+        let stuck = Boat::new(0, None).unwrap();
+        assert_eq!(None, stuck.winning_holds(7, 0));
+        assert_eq!(Some(0..=7), stuck.winning_holds(7, -1));
+
+        assert_eq!(None, Boat::new(-1, None));
+        assert_eq!(None, Boat::new(1, Some(-1)));
+        let parked = Boat::new(3, Some(0)).unwrap();
+        assert_eq!(Some(0..=7), parked.winning_holds(7, -1));
+
+        for acceleration in 0..4 {
+            for max_speed in [None, Some(0), Some(1), Some(4), Some(9)] {
+                let boat = Boat::new(acceleration, max_speed).unwrap();
+                for time in 0..25 {
+                    for dist in -2..70 {
+                        let wins = (0..=time)
+                            .filter(|&hold| boat.distance(hold, time) > dist)
+                            .collect::<Vec<_>>();
+                        let expected = wins.first().map(|&lo| lo..=*wins.last().unwrap());
+
+                        assert_eq!(expected, boat.winning_holds(time, dist));
+                        assert_eq!(
+                            expected,
+                            winning_holds_by(time, dist, |hold| boat.distance(hold, time))
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn best_allocation_test() {
+        let boat = Boat::default();
+        let races = parse_races(solution().part_1.examples[0]);
+        assert_eq!(288, boat.races_product(&races));
+
+        let dists = races.iter().map(|&(_, dist)| dist).collect::<Vec<_>>();
+        let (times, product) = boat.best_allocation(52, &dists);
+        assert_eq!(52, times.iter().sum::<i64>());
+        assert!(product >= 288);
+        assert_eq!(
+            product,
+            boat.races_product(&times.into_iter().zip(dists).collect::<Vec<_>>())
+        );
+
+        let (times, product) = boat.best_allocation(20, &[9, 9]);
+        let brute = (0..=20)
+            .map(|t| boat.ways_to_win(t, 9) * boat.ways_to_win(20 - t, 9))
+            .max()
+            .unwrap();
+        assert_eq!(brute, product);
+        assert_eq!(20, times.iter().sum::<i64>());
+
+        assert_eq!((vec![0], 0), boat.best_allocation(5, &[100]));
+    }
+
     #[test]
     fn p1_example_test() {
         let res = solution().part_1.run_example(0);
@@ -1,89 +1,361 @@
-use std::collections::HashMap;
+use std::cmp::{Ordering, Reverse};
 
 use itertools::Itertools;
 
 use crate::utils::{Day, Task};
 
+pub struct HandType {
+    name: String,
+    // group sizes, largest first
+    pattern: Vec<u8>,
+}
+
+impl HandType {
+    pub fn new(name: &str, pattern: &[u8]) -> Self {
+        let pattern = pattern.iter().copied().sorted().rev().collect();
+        Self {
+            name: name.to_string(),
+            pattern,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    // wild cards needed to turn `counts` (largest first) into this type
+    fn wilds_needed(&self, counts: &[u8]) -> u8 {
+        self.pattern
+            .iter()
+            .zip(counts.iter().chain(std::iter::repeat(&0)))
+            .map(|(&p, &c)| p.saturating_sub(c))
+            .sum()
+    }
+}
+
+pub struct Ruleset {
+    // weakest to strongest
+    order: Vec<char>,
+    wild: Vec<char>,
+    // weakest to strongest
+    hand_types: Vec<HandType>,
+}
+
+impl Ruleset {
+    pub fn new(order: &str, wild: &str, hand_types: Vec<HandType>) -> Self {
+        Self {
+            order: order.chars().collect(),
+            wild: wild.chars().collect(),
+            hand_types,
+        }
+    }
+
+    pub fn standard_hand_types() -> Vec<HandType> {
+        vec![
+            HandType::new("high card", &[1]),
+            HandType::new("one pair", &[2]),
+            HandType::new("two pair", &[2, 2]),
+            HandType::new("three of a kind", &[3]),
+            HandType::new("full house", &[3, 2]),
+            HandType::new("four of a kind", &[4]),
+            HandType::new("five of a kind", &[5]),
+        ]
+    }
+
+    pub fn standard() -> Self {
+        Self::new("23456789TJQKA", "", Self::standard_hand_types())
+    }
+
+    pub fn joker() -> Self {
+        Self::new("J23456789TQKA", "J", Self::standard_hand_types())
+    }
+
+    fn card_weight(&self, card: char) -> u8 {
+        match self.order.iter().position(|&c| c == card) {
+            Some(i) => 1 + i as u8,
+            None => unreachable!("unknown card '{card}'"),
+        }
+    }
+
+    fn is_wild(&self, card: char) -> bool {
+        self.wild.contains(&card)
+    }
+
+    pub fn hand_types(&self) -> &[HandType] {
+        &self.hand_types
+    }
+
+    // 1-based position in the hand type ladder, 0 if no type matches
+    fn hand_weight(&self, counts: &[u8], wilds: u8) -> u8 {
+        self.hand_types
+            .iter()
+            .rposition(|t| t.wilds_needed(counts) <= wilds)
+            .map_or(0, |i| 1 + i as u8)
+    }
+}
+
+// non-wild cards grouped, largest group first, and the number of wild cards
+fn card_groups(s: &str, rules: &Ruleset) -> (Vec<(char, u8)>, u8) {
+    let mut groups: Vec<(char, u8)> = Vec::with_capacity(HAND_SIZE);
+    let mut wilds = 0;
+
+    for c in s.chars() {
+        if rules.is_wild(c) {
+            wilds += 1;
+            continue;
+        }
+        match groups.iter_mut().find(|(card, _)| *card == c) {
+            Some((_, n)) => *n += 1,
+            None => groups.push((c, 1)),
+        }
+    }
+    groups.sort_by_key(|&(c, n)| Reverse((n, rules.card_weight(c))));
+
+    (groups, wilds)
+}
+
 #[derive(Ord, PartialOrd, Eq, PartialEq)]
 struct Hand {
     weight: u8,
-    cards: [u8; 5],
+    cards: Vec<u8>,
 }
 
 impl Hand {
-    fn new(s: &str, with_j: bool) -> Self {
-        let mut cards = [0; 5];
-        let mut counts = HashMap::new();
-        let mut j_count = 0;
-        let j_weight = if with_j { 1 } else { 11 };
-
-        s.chars().enumerate().for_each(|(i, c)| {
-            let val = match c {
-                c if c.is_numeric() => c.to_digit(10).unwrap(),
-                'T' => 10,
-                'J' => j_weight,
-                'Q' => 12,
-                'K' => 13,
-                'A' => 14,
-                _ => unreachable!(),
-            } as u8;
-            cards[i] = val;
-
-            if c == 'J' && with_j {
-                j_count += 1;
-            } else {
-                *counts.entry(val).or_insert(0) += 1;
-            }
-        });
-
-        let mut counts = counts.values().copied().sorted().collect_vec();
-
-        if with_j {
-            if let Some(v) = counts.last_mut() {
-                *v += j_count;
-            } else {
-                counts.push(j_count);
-            }
-        }
+    fn new(s: &str, rules: &Ruleset) -> Self {
+        let cards = s.chars().map(|c| rules.card_weight(c)).collect();
+        let (groups, wilds) = card_groups(s, rules);
+        let counts = groups.iter().map(|&(_, n)| n).collect_vec();
+        let weight = rules.hand_weight(&counts, wilds);
+
+        Self { weight, cards }
+    }
+}
 
-        let weight = match counts.as_slice() {
-            [1, 1, 1, 1, 1] => 1,
-            [1, 1, 1, 2] => 2,
-            [1, 2, 2] => 3,
-            [1, 1, 3] => 4,
-            [2, 3] => 5,
-            [1, 4] => 6,
-            [5] => 7,
-            _ => unreachable!("impossible hand type {counts:?}"),
+// the hand with every wild card replaced by the card it stands for
+fn substitute_wilds(s: &str, rules: &Ruleset) -> String {
+    let (groups, mut wilds) = card_groups(s, rules);
+    let counts = groups.iter().map(|&(_, n)| n).collect_vec();
+    let weight = rules.hand_weight(&counts, wilds);
+    if wilds == 0 || weight == 0 {
+        return s.to_string();
+    }
+
+    let mut unused = rules
+        .order
+        .iter()
+        .rev()
+        .filter(|&&c| !rules.is_wild(c) && !s.contains(c));
+    let pattern = &rules.hand_types[weight as usize - 1].pattern;
+    let mut targets = vec![];
+
+    for (i, &p) in pattern.iter().enumerate() {
+        let (card, count) = match groups.get(i) {
+            Some(&group) => group,
+            None => (*unused.next().unwrap(), 0),
         };
+        let needed = p.saturating_sub(count);
+        targets.extend(std::iter::repeat_n(card, needed as usize));
+        wilds -= needed;
+    }
+    let leftover_target = groups.first().map_or(targets[0], |&(c, _)| c);
+    targets.extend(std::iter::repeat_n(leftover_target, wilds as usize));
+
+    let mut targets = targets.into_iter();
+    s.chars()
+        .map(|c| match rules.is_wild(c) {
+            true => targets.next().unwrap(),
+            false => c,
+        })
+        .collect()
+}
 
-        if with_j && j_count > 0 && weight == 3 {
-            println!("wtf! {s}");
-        }
+pub struct RankedHand {
+    pub cards: String,
+    pub hand_type: Option<String>,
+    pub substituted: String,
+    pub rank: usize,
+    pub bid: u64,
+}
 
-        Self { cards, weight }
+impl RankedHand {
+    pub fn winnings(&self) -> u64 {
+        self.rank as u64 * self.bid
     }
 }
 
-fn total_winnings(filename: &str, with_j: bool) -> u64 {
+pub fn rank_hands(filename: &str, rules: &Ruleset) -> Vec<RankedHand> {
     crate::utils::read_lines(filename)
         .flat_map(|line| {
-            line.split_once(' ')
-                .map(|(cards, bid)| (Hand::new(cards, with_j), bid.parse::<u64>().unwrap()))
+            line.split_once(' ').map(|(cards, bid)| {
+                let hand = Hand::new(cards, rules);
+                (hand, cards.to_string(), bid.parse::<u64>().unwrap())
+            })
         })
-        .sorted()
+        .sorted_by(|(h1, ..), (h2, ..)| h1.cmp(h2))
         .enumerate()
-        .map(|(i, (_, bid))| ((i + 1) as u64) * bid)
+        .map(|(i, (hand, cards, bid))| RankedHand {
+            hand_type: hand
+                .weight
+                .checked_sub(1)
+                .map(|w| rules.hand_types[w as usize].name.clone()),
+            substituted: substitute_wilds(&cards, rules),
+            cards,
+            rank: i + 1,
+            bid,
+        })
+        .collect()
+}
+
+pub fn total_winnings(filename: &str, rules: &Ruleset) -> u64 {
+    rank_hands(filename, rules)
+        .iter()
+        .map(RankedHand::winnings)
         .sum()
 }
 
+pub fn print_rankings(filename: &str, rules: &Ruleset) {
+    let hands = rank_hands(filename, rules);
+    let type_width = rules
+        .hand_types
+        .iter()
+        .map(|t| t.name.len())
+        .max()
+        .unwrap_or(0);
+
+    println!(
+        "{:>5} | {:<5} | {:<type_width$} | {:<5} | {:>5} | {:>10}",
+        "rank", "hand", "type", "as", "bid", "winnings"
+    );
+    for hand in &hands {
+        println!(
+            "{:>5} | {:<5} | {:<type_width$} | {:<5} | {:>5} | {:>10}",
+            hand.rank,
+            hand.cards,
+            hand.hand_type.as_deref().unwrap_or("-"),
+            hand.substituted,
+            hand.bid,
+            hand.winnings()
+        );
+    }
+    println!(
+        "total: {}",
+        hands.iter().map(RankedHand::winnings).sum::<u64>()
+    );
+}
+
+const HAND_SIZE: usize = 5;
+
+pub fn all_hands(rules: &Ruleset) -> impl Iterator<Item = String> + '_ {
+    let n = rules.order.len();
+    (0..n.pow(HAND_SIZE as u32)).map(move |i| {
+        (0..HAND_SIZE as u32)
+            .rev()
+            .map(|k| rules.order[i / n.pow(k) % n])
+            .collect()
+    })
+}
+
+// hand type names weakest to strongest with the number of hands of that type
+pub fn type_distribution(rules: &Ruleset) -> Vec<(&str, usize)> {
+    let counts = all_hands(rules)
+        .map(|cards| Hand::new(&cards, rules).weight)
+        .counts();
+
+    std::iter::once("-")
+        .chain(rules.hand_types.iter().map(HandType::name))
+        .enumerate()
+        .filter_map(|(weight, name)| counts.get(&(weight as u8)).map(|&n| (name, n)))
+        .collect()
+}
+
+fn ranked_hands(rules: &Ruleset) -> Vec<(Hand, String)> {
+    all_hands(rules)
+        .map(|cards| (Hand::new(&cards, rules), cards))
+        .sorted_by(|(h1, _), (h2, _)| h1.cmp(h2))
+        .collect()
+}
+
+// every hand, weakest to strongest
+pub fn rank_order(rules: &Ruleset) -> Vec<String> {
+    ranked_hands(rules)
+        .into_iter()
+        .map(|(_, cards)| cards)
+        .collect()
+}
+
+pub fn verify_total_order(rules: &Ruleset) -> Result<(), String> {
+    let concrete = Ruleset::new(
+        &rules.order.iter().collect::<String>(),
+        "",
+        rules
+            .hand_types
+            .iter()
+            .map(|t| HandType::new(&t.name, &t.pattern))
+            .collect(),
+    );
+    let replacements = rules
+        .order
+        .iter()
+        .filter(|&&c| !rules.is_wild(c))
+        .collect_vec();
+    let hands = ranked_hands(rules);
+
+    for (hand, cards) in hands
+        .iter()
+        .filter(|(_, cards)| cards.contains(&rules.wild[..]))
+    {
+        let substituted = substitute_wilds(cards, rules);
+        if Hand::new(&substituted, &concrete).weight != hand.weight {
+            return Err(format!("{cards} is not the same type as {substituted}"));
+        }
+
+        let (groups, wilds) = card_groups(cards, rules);
+        let best_uniform = replacements
+            .iter()
+            .map(|&&r| {
+                let mut counts = groups
+                    .iter()
+                    .map(|&(c, n)| if c == r { n + wilds } else { n })
+                    .collect_vec();
+                if !groups.iter().any(|&(c, _)| c == r) {
+                    counts.push(wilds);
+                }
+                counts.sort_by_key(|&n| Reverse(n));
+                concrete.hand_weight(&counts, 0)
+            })
+            .max();
+        if best_uniform > Some(hand.weight) {
+            return Err(format!("{cards} can be made stronger"));
+        }
+    }
+
+    for ((ha, a), (hb, b)) in hands.iter().tuple_windows() {
+        let first_difference = a
+            .chars()
+            .zip(b.chars())
+            .map(|(ca, cb)| rules.card_weight(ca).cmp(&rules.card_weight(cb)))
+            .find(|o| o.is_ne());
+
+        let is_ordered = match ha.weight.cmp(&hb.weight) {
+            Ordering::Less => true,
+            Ordering::Equal => first_difference == Some(Ordering::Less),
+            Ordering::Greater => false,
+        };
+        if !is_ordered {
+            return Err(format!("{a} is ranked below {b}"));
+        }
+    }
+
+    Ok(())
+}
+
 pub fn solution() -> Day<u64, u64> {
     fn part_1(filename: &str) -> u64 {
-        total_winnings(filename, false)
+        total_winnings(filename, &Ruleset::standard())
     }
 
     fn part_2(filename: &str) -> u64 {
-        total_winnings(filename, true)
+        total_winnings(filename, &Ruleset::joker())
     }
 
     Day {
@@ -117,7 +389,114 @@ mod d07_tests {
     }
 
     fn weight(cards: &str) -> u8 {
-        Hand::new(cards, true).weight
+        Hand::new(cards, &Ruleset::joker()).weight
+    }
+
+    #[test]
+    fn rank_hands_test() {
+        let filename = solution().part_2.examples[0];
+        let hands = rank_hands(filename, &Ruleset::joker());
+
+        let explained = hands
+            .iter()
+            .map(|h| {
+                let hand_type = h.hand_type.as_deref().unwrap();
+                (h.cards.as_str(), hand_type, h.substituted.as_str(), h.rank)
+            })
+            .collect_vec();
+        assert_eq!(explained, vec![
+            ("32T3K", "one pair", "32T3K", 1),
+            ("KK677", "two pair", "KK677", 2),
+            ("T55J5", "four of a kind", "T5555", 3),
+            ("QQQJA", "four of a kind", "QQQQA", 4),
+            ("KTJJT", "four of a kind", "KTTTT", 5),
+        ]);
+        assert_eq!(5905, hands.iter().map(RankedHand::winnings).sum::<u64>());
+    }
+
+    #[test]
+    fn substitute_wilds_test() {
+        let rules = Ruleset::joker();
+        assert_eq!("AAAAA", substitute_wilds("JJJJJ", &rules));
+        assert_eq!("22222", substitute_wilds("2JJJJ", &rules));
+        assert_eq!("23455", substitute_wilds("2345J", &rules));
+        assert_eq!("33322", substitute_wilds("33J22", &rules));
+
+        let standard = Ruleset::standard();
+        for hand in ["J6QT6", "66AAJ", "JJQQQ", "9J4AJ", "AJJ97"] {
+            let substituted = substitute_wilds(hand, &rules);
+            assert!(!substituted.contains('J'));
+            assert_eq!(
+                Hand::new(hand, &rules).weight,
+                Hand::new(&substituted, &standard).weight
+            );
+        }
+    }
+
+    #[test]
+    fn type_distribution_test() {
+        let standard = Ruleset::standard();
+        let distribution = type_distribution(&standard);
+        assert_eq!(distribution, vec![
+            ("high card", 154440),
+            ("one pair", 171600),
+            ("two pair", 25740),
+            ("three of a kind", 17160),
+            ("full house", 1560),
+            ("four of a kind", 780),
+            ("five of a kind", 13),
+        ]);
+
+        let joker = Ruleset::joker();
+        let distribution = type_distribution(&joker);
+        assert_eq!(distribution, vec![
+            ("high card", 95040),
+            ("one pair", 178200),
+            ("two pair", 19800),
+            ("three of a kind", 66000),
+            ("full house", 3300),
+            ("four of a kind", 8580),
+            ("five of a kind", 373),
+        ]);
+    }
+
+    #[test]
+    fn rank_order_test() {
+        let hands = rank_order(&Ruleset::joker());
+        assert_eq!(13usize.pow(5), hands.len());
+        assert_eq!("23456", hands[0]);
+        assert_eq!("AAAAA", hands[hands.len() - 1]);
+        assert_eq!("JJJJJ", hands[hands.len() - 373]);
+    }
+
+    #[test]
+    fn total_order_test() {
+        assert_eq!(Ok(()), verify_total_order(&Ruleset::standard()));
+        assert_eq!(Ok(()), verify_total_order(&Ruleset::joker()));
+    }
+
+    #[test]
+    fn custom_ruleset_test() {
+        let filename = solution().part_1.examples[0];
+
+        let twos_wild = Ruleset::new("2J3456789TQKA", "2J", Ruleset::standard_hand_types());
+        assert_eq!(4, Hand::new("2J345", &twos_wild).weight);
+        assert_eq!(7, Hand::new("22JJ2", &twos_wild).weight);
+        assert_eq!(6, Hand::new("2JKK3", &twos_wild).weight);
+
+        let no_two_pair = Ruleset::new("23456789TJQKA", "", vec![
+            HandType::new("high card", &[1]),
+            HandType::new("pair", &[2]),
+            HandType::new("three of a kind", &[3]),
+        ]);
+        assert_eq!(2, Hand::new("KK22Q", &no_two_pair).weight);
+        assert_eq!(3, Hand::new("KKK22", &no_two_pair).weight);
+        assert_eq!(3, Hand::new("KKKK2", &no_two_pair).weight);
+
+        let reversed = Ruleset::new("AKQJT98765432", "", Ruleset::standard_hand_types());
+        assert!(Hand::new("22345", &reversed) > Hand::new("AKQJT", &reversed));
+        assert_eq!(6440, total_winnings(filename, &Ruleset::standard()));
+        assert_ne!(6440, total_winnings(filename, &reversed));
     }
 
     #[test]
@@ -2,67 +2,170 @@ use itertools::Itertools;
 
 use crate::utils::{Day, Task};
 
-fn expanding_distances(filename: &str, expansion_factor: usize) -> usize {
-    let mut map = crate::utils::read_lines(filename)
+// sum of |a - b| over all pairs
+fn pairwise_sum(mut values: Vec<usize>) -> usize {
+    values.sort_unstable();
+    values
+        .iter()
         .enumerate()
-        .map(|(y, line)| {
-            line.chars()
-                .enumerate()
-                .map(|(x, c)| match c {
-                    '#' => Some((x, y)),
-                    '.' => None,
-                    _ => unreachable!(),
-                })
-                .collect_vec()
+        .fold((0, 0), |(sum, prefix), (i, &v)| {
+            (sum + v * i - prefix, prefix + v)
         })
-        .collect_vec();
-
-    let mut blank_rows = 0;
-    for row in map.iter_mut() {
-        let galaxies_in_row = row
-            .iter_mut()
-            .flatten()
-            .map(|galaxy| galaxy.1 += blank_rows)
-            .count();
-
-        if galaxies_in_row == 0 {
-            blank_rows += expansion_factor - 1;
+        .0
+}
+
+pub struct Universe {
+    galaxies: Vec<(usize, usize)>,
+    empty_rows: Vec<usize>,
+    empty_cols: Vec<usize>,
+}
+
+impl Universe {
+    pub fn from_file(filename: &str) -> Self {
+        let image = crate::utils::read_lines(filename)
+            .map(|line| {
+                line.chars()
+                    .map(|c| match c {
+                        '#' => true,
+                        '.' => false,
+                        _ => unreachable!(),
+                    })
+                    .collect_vec()
+            })
+            .collect_vec();
+
+        let galaxies = image
+            .iter()
+            .enumerate()
+            .flat_map(|(y, row)| row.iter().positions(|&g| g).map(move |x| (x, y)))
+            .collect();
+        let empty_rows = image.iter().positions(|row| !row.contains(&true)).collect();
+        let empty_cols = (0..image[0].len())
+            .filter(|&x| image.iter().all(|row| !row[x]))
+            .collect();
+
+        Self {
+            galaxies,
+            empty_rows,
+            empty_cols,
         }
     }
 
-    let mut blank_cols = 0;
-    for x in 0..map[0].len() {
-        let galaxies_in_col = map
-            .iter_mut()
-            .flat_map(|row| &mut row[x])
-            .map(|galaxy| galaxy.0 += blank_cols)
-            .count();
+    fn empty_before(coord: usize, empty: &[usize]) -> usize {
+        empty.partition_point(|&e| e < coord)
+    }
 
-        if galaxies_in_col == 0 {
-            blank_cols += expansion_factor - 1;
-        }
+    fn expand(coord: usize, empty: &[usize], expansion_factor: usize) -> usize {
+        coord + Self::empty_before(coord, empty) * (expansion_factor - 1)
     }
 
-    let galaxies = map.iter().flatten().flatten().collect_vec();
+    pub fn len(&self) -> usize {
+        self.galaxies.len()
+    }
 
-    galaxies
-        .iter()
-        .enumerate()
-        .flat_map(|(i, (x1, y1))| {
-            galaxies
-                .iter()
-                .skip(i + 1)
-                .map(|(x2, y2)| x1.abs_diff(*x2) + y1.abs_diff(*y2))
+    pub fn is_empty(&self) -> bool {
+        self.galaxies.is_empty()
+    }
+
+    pub fn galaxy(&self, i: usize, expansion_factor: usize) -> (usize, usize) {
+        let (x, y) = self.galaxies[i];
+        (
+            Self::expand(x, &self.empty_cols, expansion_factor),
+            Self::expand(y, &self.empty_rows, expansion_factor),
+        )
+    }
+
+    pub fn galaxies(&self, expansion_factor: usize) -> Vec<(usize, usize)> {
+        (0..self.len())
+            .map(|i| self.galaxy(i, expansion_factor))
+            .collect()
+    }
+
+    pub fn distance(&self, a: usize, b: usize, expansion_factor: usize) -> usize {
+        let (x1, y1) = self.galaxy(a, expansion_factor);
+        let (x2, y2) = self.galaxy(b, expansion_factor);
+        x1.abs_diff(x2) + y1.abs_diff(y2)
+    }
+
+    // (index, distance) of the closest other galaxy
+    pub fn nearest_neighbour(&self, i: usize, expansion_factor: usize) -> Option<(usize, usize)> {
+        (0..self.len())
+            .filter(|&j| j != i)
+            .map(|j| (j, self.distance(i, j, expansion_factor)))
+            .min_by_key(|&(_, d)| d)
+    }
+
+    // (index, index, distance) of the most distant pair
+    pub fn farthest_pair(&self, expansion_factor: usize) -> Option<(usize, usize, usize)> {
+        let galaxies = self.galaxies(expansion_factor);
+
+        // manhattan distance is the largest spread of either x + y or x - y
+        [
+            |x: usize, y: usize| (x + y) as isize,
+            |x: usize, y: usize| x as isize - y as isize,
+        ]
+        .into_iter()
+        .filter_map(|projection| {
+            let projected = || galaxies.iter().map(|&(x, y)| projection(x, y));
+            let (min, _) = projected().enumerate().min_by_key(|&(_, p)| p)?;
+            let (max, _) = projected().enumerate().max_by_key(|&(_, p)| p)?;
+            Some((min.min(max), min.max(max)))
         })
-        .sum()
+        .filter(|&(a, b)| a != b)
+        .map(|(a, b)| (a, b, self.distance(a, b, expansion_factor)))
+        .max_by_key(|&(.., d)| d)
+    }
+
+    // distance between galaxies `i` and `j` is `a + b * (expansion_factor - 1)`
+    pub fn pair_coefficients(&self, i: usize, j: usize) -> (usize, usize) {
+        let (x1, y1) = self.galaxies[i];
+        let (x2, y2) = self.galaxies[j];
+        let empty_x = |x| Self::empty_before(x, &self.empty_cols);
+        let empty_y = |y| Self::empty_before(y, &self.empty_rows);
+
+        let a = x1.abs_diff(x2) + y1.abs_diff(y2);
+        let b = empty_x(x1).abs_diff(empty_x(x2)) + empty_y(y1).abs_diff(empty_y(y2));
+        (a, b)
+    }
+
+    // sum of all pairwise distances is `a + b * (expansion_factor - 1)`
+    pub fn distance_coefficients(&self) -> (usize, usize) {
+        let (xs, ys): (Vec<_>, Vec<_>) = self.galaxies.iter().copied().unzip();
+        let empty_xs = xs
+            .iter()
+            .map(|&x| Self::empty_before(x, &self.empty_cols))
+            .collect();
+        let empty_ys = ys
+            .iter()
+            .map(|&y| Self::empty_before(y, &self.empty_rows))
+            .collect();
+
+        let a = pairwise_sum(xs) + pairwise_sum(ys);
+        let b = pairwise_sum(empty_xs) + pairwise_sum(empty_ys);
+        (a, b)
+    }
+
+    pub fn distance_sum(&self, expansion_factor: usize) -> usize {
+        let (a, b) = self.distance_coefficients();
+        a + b * (expansion_factor - 1)
+    }
+
+    pub fn total_distance(&self, expansion_factor: u128) -> u128 {
+        let (a, b) = self.distance_coefficients();
+        a as u128 + b as u128 * (expansion_factor - 1)
+    }
 }
 
-pub fn solution() -> Day<usize, usize> {
-    fn part_1(filename: &str) -> usize {
+fn expanding_distances(filename: &str, expansion_factor: u128) -> u128 {
+    Universe::from_file(filename).total_distance(expansion_factor)
+}
+
+pub fn solution() -> Day<u128, u128> {
+    fn part_1(filename: &str) -> u128 {
         expanding_distances(filename, 2)
     }
 
-    fn part_2(filename: &str) -> usize {
+    fn part_2(filename: &str) -> u128 {
         expanding_distances(filename, 1000000)
     }
 
@@ -101,4 +204,65 @@ mod d11_tests {
         let res = expanding_distances(filename, 100);
         assert_eq!(8410, res);
     }
+
+    #[test]
+    fn distance_coefficients_test() {
+        let universe = Universe::from_file(solution().part_1.examples[0]);
+
+        let (a, b) = universe.distance_coefficients();
+        assert_eq!((292, 82), (a, b));
+
+        let pair_sums = (0..universe.len())
+            .tuple_combinations()
+            .map(|(i, j)| universe.pair_coefficients(i, j))
+            .fold((0, 0), |(a, b), (pa, pb)| (a + pa, b + pb));
+        assert_eq!((a, b), pair_sums);
+
+        for (i, j) in [(4, 8), (0, 6), (2, 5), (7, 8)] {
+            let (pa, pb) = universe.pair_coefficients(i, j);
+            for factor in [1, 2, 10, 100] {
+                assert_eq!(universe.distance(i, j, factor), pa + pb * (factor - 1));
+            }
+        }
+
+        assert_eq!(374, universe.total_distance(2));
+        assert_eq!(
+            82_000_000_000_000_000_210,
+            universe.total_distance(1_000_000_000_000_000_000)
+        );
+    }
+
+    #[test]
+    fn universe_queries_test() {
+        let universe = Universe::from_file(solution().part_1.examples[0]);
+        assert_eq!(9, universe.len());
+
+        assert_eq!((4, 0), universe.galaxy(0, 2));
+        assert_eq!((0, 11), universe.galaxy(7, 2));
+        assert_eq!(9, universe.distance(4, 8, 2));
+        assert_eq!(15, universe.distance(0, 6, 2));
+        assert_eq!(17, universe.distance(2, 5, 2));
+        assert_eq!(5, universe.distance(7, 8, 2));
+
+        for factor in [1, 2, 10, 100] {
+            let pairs = (0..universe.len())
+                .tuple_combinations()
+                .map(|(a, b)| (a, b, universe.distance(a, b, factor)))
+                .collect_vec();
+
+            let brute_sum = pairs.iter().map(|&(.., d)| d).sum::<usize>();
+            assert_eq!(brute_sum, universe.distance_sum(factor));
+
+            let brute_max = pairs.iter().map(|&(.., d)| d).max();
+            assert_eq!(brute_max, universe.farthest_pair(factor).map(|(.., d)| d));
+
+            for i in 0..universe.len() {
+                let (j, d) = universe.nearest_neighbour(i, factor).unwrap();
+                assert_eq!(d, universe.distance(i, j, factor));
+                assert!(pairs
+                    .iter()
+                    .all(|&(a, b, d2)| !(a == i || b == i) || d <= d2));
+            }
+        }
+    }
 }
@@ -1,9 +1,13 @@
-use std::{collections::HashMap, fmt::Formatter};
+use std::{fmt::Formatter, ops::Sub};
+
+use num::{One, Zero};
 
 use crate::utils::{Day, Task};
 
+pub mod nonogram;
+
 #[derive(Eq, PartialEq, Copy, Clone)]
-enum Spring {
+pub enum Spring {
     Good,
     Bad,
     Unknown,
@@ -42,30 +46,26 @@ impl From<char> for Spring {
     }
 }
 
-struct SpringLine {
+pub struct SpringLine {
     springs: Vec<Spring>,
     bad_spans: Vec<usize>,
-    cache: HashMap<(usize, usize), usize>,
 }
 
 impl SpringLine {
-    fn expand(
-        springs: Vec<Spring>,
-        bas_spans: Vec<usize>,
-        times: usize,
-    ) -> (Vec<Spring>, Vec<usize>) {
-        let new_springs = std::iter::repeat(springs)
-            .take(times)
-            .intersperse(vec![Spring::Unknown])
+    pub fn new(springs: Vec<Spring>, bad_spans: Vec<usize>) -> Self {
+        Self { springs, bad_spans }
+    }
+
+    pub fn unfold(&self, times: usize, separator: Spring) -> Self {
+        let springs = std::iter::repeat_n(self.springs.clone(), times)
+            .intersperse(vec![separator])
             .flatten()
             .collect();
 
-        let new_spans = std::iter::repeat(bas_spans).take(times).flatten().collect();
-
-        (new_springs, new_spans)
+        Self::new(springs, self.bad_spans.repeat(times))
     }
 
-    fn from_line(line: &str, repeat: usize) -> Self {
+    pub fn from_line(line: &str, repeat: usize) -> Self {
         use itertools::Itertools;
 
         let (springs_line, bad_spans_line) = line.split_once(' ').unwrap();
@@ -85,106 +85,198 @@ impl SpringLine {
 
         let bad_spans = bad_spans_line.split(',').flat_map(str::parse).collect();
 
-        let (springs, bad_spans) = Self::expand(springs, bad_spans, repeat);
+        Self::new(springs, bad_spans).unfold(repeat, Spring::Unknown)
+    }
 
-        Self {
-            springs,
-            bad_spans,
-            cache: HashMap::new(),
+    fn possibly_bad_runs(&self) -> Vec<usize> {
+        let mut runs = vec![0; self.springs.len() + 1];
+        for (i, spring) in self.springs.iter().enumerate().rev() {
+            if spring.possibly_bad() {
+                runs[i] = runs[i + 1] + 1;
+            }
         }
+        runs
     }
 
-    fn bad_len(&self) -> usize {
-        self.bad_spans.len()
+    // ways[i] - variants of springs[i..] without any bad spans
+    fn no_span_ways<T: Clone + Zero + One>(&self) -> Vec<T> {
+        let mut ways = vec![T::zero(); self.springs.len() + 1];
+        for i in (0..ways.len()).rev() {
+            match self.springs.get(i) {
+                Some(Spring::Bad) => break,
+                _ => ways[i] = T::one(),
+            }
+        }
+        ways
     }
 
-    fn spring_at(&self, i: usize) -> Option<&Spring> {
-        self.springs.get(i)
-    }
+    // ways[i] - variants of springs[i..] starting with `span`, followed by spans counted in `next`
+    fn span_ways<T: Clone + Zero>(&self, span: usize, runs: &[usize], next: &[T]) -> Vec<T> {
+        let n = self.springs.len();
+        let mut ways = vec![T::zero(); n + 1];
+
+        for i in (0..n).rev() {
+            let mut res = match self.springs[i] {
+                Spring::Bad => T::zero(),
+                _ => ways[i + 1].clone(),
+            };
+            if runs[i] >= span {
+                match self.springs.get(i + span) {
+                    None => res = res + next[n].clone(),
+                    Some(Spring::Bad) => {}
+                    Some(_) => res = res + next[i + span + 1].clone(),
+                }
+            }
+            ways[i] = res;
+        }
 
-    fn bad_span_at(&self, i: usize) -> Option<&usize> {
-        self.bad_spans.get(i)
+        ways
     }
 
-    fn possibly_bad_count(&self, start: usize, cap: usize) -> usize {
-        self.springs[start..]
+    pub fn count_variants_as<T: Clone + Zero + One>(&self) -> T {
+        let runs = self.possibly_bad_runs();
+        self.bad_spans
             .iter()
-            .take(cap)
-            .take_while(|spring| spring.possibly_bad())
-            .count()
+            .rev()
+            .fold(self.no_span_ways(), |next, &span| {
+                self.span_ways(span, &runs, &next)
+            })
+            .swap_remove(0)
     }
 
-    fn next_bad(&self, start: usize, cap: usize) -> Option<usize> {
-        self.springs[start..]
-            .iter()
-            .take(cap)
-            .position(|spring| matches!(spring, Spring::Bad))
+    pub fn count_variants(&self) -> u128 {
+        self.count_variants_as()
     }
 
-    fn count_variants(&mut self, si: usize, bi: usize) -> usize {
-        if let Some(res) = self.cache.get(&(si, bi)) {
-            return *res;
+    // table[j][i] - variants of springs[i..] with bad_spans[j..]
+    fn ways_table<T: Clone + Zero + One>(&self) -> Vec<Vec<T>> {
+        let runs = self.possibly_bad_runs();
+        let mut table = vec![self.no_span_ways()];
+        for &span in self.bad_spans.iter().rev() {
+            let next = self.span_ways(span, &runs, table.last().unwrap());
+            table.push(next);
         }
+        table.reverse();
+        table
+    }
 
-        let res = match (self.spring_at(si), self.bad_span_at(bi)) {
-            (_, None) if self.springs[si..].contains(&Spring::Bad) => 0,
-            (_, None) => 1,
-            (None, Some(_)) => 0,
-            (Some(Spring::Good), Some(_)) => self.count_variants(si + 1, bi),
-            (Some(Spring::Bad), Some(&n)) => {
-                let possibly_bad_count = self.possibly_bad_count(si, n);
-
-                match self.spring_at(si + n) {
-                    _ if possibly_bad_count != n => 0,
-                    None if self.bad_len() - bi > 1 => 0,
-                    None => 1,
-                    Some(Spring::Bad) => 0,
-                    Some(_) => self.count_variants(si + n + 1, bi + 1),
-                }
+    fn variant_at<T>(&self, table: &[Vec<T>], mut index: T) -> Vec<Spring>
+    where
+        T: Clone + Zero + Ord + Sub<Output = T>,
+    {
+        let n = self.springs.len();
+        let mut variant = Vec::with_capacity(n);
+        let (mut i, mut j) = (0, 0);
+
+        while i < n {
+            if j == self.bad_spans.len() {
+                variant.resize(n, Spring::Good);
+                break;
             }
-            (Some(Spring::Unknown), Some(&n)) => {
-                let possibly_bad_count = self.possibly_bad_count(si, n);
-
-                match self.spring_at(si + n) {
-                    _ if possibly_bad_count != n => {
-                        let continue_from = self
-                            .next_bad(si, possibly_bad_count)
-                            .unwrap_or(possibly_bad_count);
-                        self.count_variants(si + continue_from, bi)
-                    }
-                    None if self.bad_len() - bi > 1 => 0,
-                    None => 1,
-                    Some(Spring::Bad) => self.count_variants(si + 1, bi),
-                    Some(_) => {
-                        let starting_here = self.count_variants(si + n + 1, bi + 1);
-                        let skipping_it = self.count_variants(si + 1, bi);
-                        starting_here + skipping_it
-                    }
-                }
+
+            let good_here = match self.springs[i] {
+                Spring::Bad => T::zero(),
+                _ => table[j][i + 1].clone(),
+            };
+            if index < good_here {
+                variant.push(Spring::Good);
+                i += 1;
+                continue;
+            }
+            index = index - good_here;
+
+            let span = self.bad_spans[j];
+            variant.extend(std::iter::repeat_n(Spring::Bad, span));
+            i += span;
+            j += 1;
+            if i < n {
+                variant.push(Spring::Good);
+                i += 1;
             }
-        };
+        }
 
-        self.cache.insert((si, bi), res);
+        variant
+    }
+
+    pub fn variant_as<T>(&self, index: T) -> Option<Vec<Spring>>
+    where
+        T: Clone + Zero + One + Ord + Sub<Output = T>,
+    {
+        let table = self.ways_table();
+        (index < table[0][0]).then(|| self.variant_at(&table, index))
+    }
 
-        res
+    pub fn variant(&self, index: u128) -> Option<Vec<Spring>> {
+        self.variant_as(index)
+    }
+
+    pub fn variants_as<T>(&self) -> impl Iterator<Item = Vec<Spring>> + '_
+    where
+        T: Clone + Zero + One + Ord + Sub<Output = T> + 'static,
+    {
+        let table = self.ways_table::<T>();
+        let count = table[0][0].clone();
+        std::iter::successors(Some(T::zero()), |index| Some(index.clone() + T::one()))
+            .take_while(move |index| *index < count)
+            .map(move |index| self.variant_at(&table, index))
+    }
+
+    pub fn variants(&self) -> impl Iterator<Item = Vec<Spring>> + '_ {
+        self.variants_as::<u128>()
+    }
+
+    // `below(n)` is expected to return a uniformly distributed value in 0..n
+    pub fn sample_as<T>(&self, below: impl FnOnce(T) -> T) -> Option<Vec<Spring>>
+    where
+        T: Clone + Zero + One + Ord + Sub<Output = T>,
+    {
+        let table = self.ways_table::<T>();
+        let count = table[0][0].clone();
+        if count.is_zero() {
+            return None;
+        }
+
+        let index = below(count.clone());
+        assert!(index < count, "sampled index is out of range");
+        Some(self.variant_at(&table, index))
+    }
+
+    pub fn sample(&self, below: impl FnOnce(u128) -> u128) -> Option<Vec<Spring>> {
+        self.sample_as(below)
+    }
+
+    pub fn is_variant(&self, springs: &[Spring]) -> bool {
+        use itertools::Itertools;
+
+        let fits = springs.len() == self.springs.len()
+            && springs
+                .iter()
+                .zip(&self.springs)
+                .all(|(s, p)| *s != Spring::Unknown && (*p == Spring::Unknown || s == p));
+
+        fits && springs
+            .iter()
+            .group_by(|s| **s == Spring::Bad)
+            .into_iter()
+            .filter(|(bad, _)| *bad)
+            .map(|(_, span)| span.count())
+            .eq(self.bad_spans.iter().copied())
     }
 }
 
-fn part_1(filename: &str) -> usize {
+fn part_1(filename: &str) -> u128 {
     crate::utils::read_lines(filename)
-        .map(|line| SpringLine::from_line(&line, 1))
-        .map(|mut line| line.count_variants(0, 0))
+        .map(|line| SpringLine::from_line(&line, 1).count_variants())
         .sum()
 }
 
-fn part_2(filename: &str) -> usize {
+fn part_2(filename: &str) -> u128 {
     crate::utils::read_lines(filename)
-        .map(|line| SpringLine::from_line(&line, 5))
-        .map(|mut line| line.count_variants(0, 0))
+        .map(|line| SpringLine::from_line(&line, 5).count_variants())
         .sum()
 }
 
-pub fn solution() -> Day<usize, usize> {
+pub fn solution() -> Day<u128, u128> {
     Day {
         part_1: Task {
             examples: vec!["./inputs/day_12/example_01.txt"],
@@ -210,9 +302,8 @@ mod d12_tests {
         assert_eq!(21, res);
     }
 
-    fn from_line(line: &str) -> usize {
-        let mut spring_line = SpringLine::from_line(line, 1);
-        spring_line.count_variants(0, 0)
+    fn from_line(line: &str) -> u128 {
+        SpringLine::from_line(line, 1).count_variants()
     }
 
     #[test]
@@ -224,6 +315,70 @@ mod d12_tests {
         assert_eq!(6, from_line("?#???.?#???#?#????#? 2,1,3,3,5"));
     }
 
+    #[test]
+    fn unfold_test() {
+        let line = SpringLine::from_line("?###???????? 3,2,1", 1);
+        assert_eq!(506250, line.unfold(5, Spring::Unknown).count_variants());
+        assert_eq!(1000, line.unfold(3, Spring::Good).count_variants());
+
+        let unfolded = line.unfold(40, Spring::Unknown);
+        let expected = num::BigUint::from(10u32) * num::BigUint::from(15u32).pow(39);
+        assert_eq!(expected, unfolded.count_variants_as());
+    }
+
+    #[test]
+    fn big_unfold_variants_test() {
+        let unfolded = SpringLine::from_line("?###???????? 3,2,1", 1).unfold(40, Spring::Unknown);
+        let count: num::BigUint = unfolded.count_variants_as();
+
+        let first = unfolded.sample_as(|_| num::BigUint::zero()).unwrap();
+        assert!(unfolded.is_variant(&first));
+        assert_eq!(
+            Some(&first),
+            unfolded.variants_as::<num::BigUint>().next().as_ref()
+        );
+
+        let last = unfolded
+            .sample_as(|count: num::BigUint| count - 1u32)
+            .unwrap();
+        assert!(unfolded.is_variant(&last));
+        assert_eq!(Some(last), unfolded.variant_as(count.clone() - 1u32));
+        assert_eq!(None, unfolded.variant_as(count));
+    }
+
+    #[test]
+    fn variants_test() {
+        let line = SpringLine::from_line("?###???????? 3,2,1", 1);
+        let variants = line.variants().collect::<Vec<_>>();
+
+        assert_eq!(variants.len() as u128, line.count_variants());
+        assert!(variants.iter().all(|v| line.is_variant(v)));
+        assert!(variants.windows(2).all(|w| w[0] != w[1]));
+        assert_eq!(
+            ".###....##.#",
+            variants[0]
+                .iter()
+                .map(Spring::to_string)
+                .collect::<String>()
+        );
+
+        let unfolded = line.unfold(5, Spring::Unknown);
+        let last = unfolded.variant(506249).unwrap();
+        assert!(unfolded.is_variant(&last));
+        assert_eq!(None, unfolded.variant(506250));
+        assert_eq!(Some(last), unfolded.sample(|count| count - 1));
+
+        let broken = SpringLine::from_line("### 1", 1);
+        assert_eq!(0, broken.variants().count());
+        assert_eq!(None, broken.sample(|_| unreachable!()));
+    }
+
+    #[test]
+    #[should_panic(expected = "sampled index is out of range")]
+    fn sample_out_of_range_test() {
+        SpringLine::from_line("???.### 1,1,3", 1).sample(|count| count);
+    }
+
     #[test]
     fn p2_example_test() {
         let solution = solution();
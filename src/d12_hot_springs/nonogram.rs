@@ -0,0 +1,303 @@
+use std::{fmt::Formatter, str::FromStr};
+
+use itertools::Itertools;
+
+use super::{Spring, SpringLine};
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Grid {
+    cells: Vec<Vec<Spring>>,
+}
+
+impl Grid {
+    fn unknown(width: usize, height: usize) -> Self {
+        Self {
+            cells: vec![vec![Spring::Unknown; width]; height],
+        }
+    }
+
+    pub fn cells(&self) -> &[Vec<Spring>] {
+        &self.cells
+    }
+
+    fn column(&self, x: usize) -> Vec<Spring> {
+        self.cells.iter().map(|row| row[x]).collect()
+    }
+
+    fn set_column(&mut self, x: usize, column: Vec<Spring>) {
+        self.cells
+            .iter_mut()
+            .zip(column)
+            .for_each(|(row, cell)| row[x] = cell);
+    }
+
+    fn first_unknown(&self) -> Option<(usize, usize)> {
+        self.cells.iter().enumerate().find_map(|(y, row)| {
+            row.iter()
+                .position(|&cell| cell == Spring::Unknown)
+                .map(|x| (x, y))
+        })
+    }
+}
+
+impl std::fmt::Display for Grid {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for row in &self.cells {
+            row.iter().try_for_each(|cell| write!(f, "{cell}"))?;
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Grid {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let cells = s
+            .lines()
+            .map(|line| {
+                line.chars()
+                    .map(|c| match c {
+                        '.' | '#' | '?' => Ok(Spring::from(c)),
+                        _ => Err(()),
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        match cells.iter().map(Vec::len).all_equal() {
+            true => Ok(Self { cells }),
+            false => Err(()),
+        }
+    }
+}
+
+// Returns `None` on contradiction, otherwise whether any cell was resolved.
+fn solve_line(cells: &mut [Spring], clues: &[usize]) -> Option<bool> {
+    let count_with =
+        |cells: &[Spring]| SpringLine::new(cells.to_vec(), clues.to_vec()).count_variants();
+
+    if count_with(cells) == 0 {
+        return None;
+    }
+
+    let mut changed = false;
+    for i in 0..cells.len() {
+        if cells[i] != Spring::Unknown {
+            continue;
+        }
+
+        cells[i] = Spring::Bad;
+        let can_be_bad = count_with(cells) > 0;
+        cells[i] = Spring::Good;
+        let can_be_good = count_with(cells) > 0;
+
+        cells[i] = match (can_be_bad, can_be_good) {
+            (true, true) => Spring::Unknown,
+            (true, false) => Spring::Bad,
+            (false, true) => Spring::Good,
+            (false, false) => return None,
+        };
+        changed |= cells[i] != Spring::Unknown;
+    }
+
+    Some(changed)
+}
+
+pub struct Nonogram {
+    rows: Vec<Vec<usize>>,
+    columns: Vec<Vec<usize>>,
+}
+
+impl Nonogram {
+    pub fn new(rows: Vec<Vec<usize>>, columns: Vec<Vec<usize>>) -> Self {
+        Self { rows, columns }
+    }
+
+    pub fn from_grid(grid: &Grid) -> Self {
+        fn clues(line: &[Spring]) -> Vec<usize> {
+            line.iter()
+                .group_by(|&&cell| cell == Spring::Bad)
+                .into_iter()
+                .filter(|(bad, _)| *bad)
+                .map(|(_, span)| span.count())
+                .collect()
+        }
+
+        let width = grid.cells.first().map_or(0, Vec::len);
+        let rows = grid.cells.iter().map(|row| clues(row)).collect();
+        let columns = (0..width).map(|x| clues(&grid.column(x))).collect();
+
+        Self::new(rows, columns)
+    }
+
+    fn propagate(&self, grid: &mut Grid) -> bool {
+        loop {
+            let mut changed = false;
+
+            for (row, clues) in grid.cells.iter_mut().zip(&self.rows) {
+                match solve_line(row, clues) {
+                    None => return false,
+                    Some(c) => changed |= c,
+                }
+            }
+
+            for (x, clues) in self.columns.iter().enumerate() {
+                let mut column = grid.column(x);
+                match solve_line(&mut column, clues) {
+                    None => return false,
+                    Some(c) => changed |= c,
+                }
+                grid.set_column(x, column);
+            }
+
+            if !changed {
+                return true;
+            }
+        }
+    }
+
+    fn search(&self, mut grid: Grid, limit: usize, found: &mut Vec<Grid>) {
+        if found.len() >= limit || !self.propagate(&mut grid) {
+            return;
+        }
+
+        match grid.first_unknown() {
+            None => found.push(grid),
+            Some((x, y)) => {
+                for cell in [Spring::Bad, Spring::Good] {
+                    let mut guess = grid.clone();
+                    guess.cells[y][x] = cell;
+                    self.search(guess, limit, found);
+                }
+            }
+        }
+    }
+
+    pub fn solutions(&self, limit: usize) -> Vec<Grid> {
+        let mut found = vec![];
+        let grid = Grid::unknown(self.columns.len(), self.rows.len());
+        self.search(grid, limit, &mut found);
+        found
+    }
+
+    pub fn solve(&self) -> Option<Grid> {
+        self.solutions(1).pop()
+    }
+
+    pub fn is_unique(&self) -> bool {
+        self.solutions(2).len() == 1
+    }
+}
+
+// A subset of the `.non` format: `rows` and `columns` sections with one comma separated
+// clue per line (`0` for an empty line), `width` and `height` are checked if present.
+impl FromStr for Nonogram {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut rows = vec![];
+        let mut columns = vec![];
+        let mut size = (None, None);
+        let mut section = None;
+
+        for line in s.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            let (keyword, value) = line.split_once(' ').unwrap_or((line, ""));
+            match keyword {
+                "rows" => section = Some(&mut rows),
+                "columns" => section = Some(&mut columns),
+                "width" => size.0 = Some(value.parse::<usize>().map_err(|_| ())?),
+                "height" => size.1 = Some(value.parse::<usize>().map_err(|_| ())?),
+                _ if line.starts_with(|c: char| c.is_ascii_digit()) => {
+                    let clues = line
+                        .split(',')
+                        .map(|n| n.trim().parse::<usize>())
+                        .filter(|n| n != &Ok(0))
+                        .collect::<Result<Vec<_>, _>>()
+                        .map_err(|_| ())?;
+                    section.as_mut().ok_or(())?.push(clues);
+                }
+                _ => section = None,
+            }
+        }
+
+        let matches_size =
+            |expected: Option<usize>, actual: usize| expected.is_none_or(|n| n == actual);
+        if rows.is_empty()
+            || columns.is_empty()
+            || !matches_size(size.0, columns.len())
+            || !matches_size(size.1, rows.len())
+        {
+            return Err(());
+        }
+
+        Ok(Self::new(rows, columns))
+    }
+}
+
+#[cfg(test)]
+mod nonogram_tests {
+    use super::*;
+
+    #[test]
+    fn solve_test() {
+        let grid: Grid = ".###.\n#...#\n#####\n#...#\n#...#\n".parse().unwrap();
+        let nonogram = Nonogram::from_grid(&grid);
+
+        assert_eq!(nonogram.rows, vec![
+            vec![3],
+            vec![1, 1],
+            vec![5],
+            vec![1, 1],
+            vec![1, 1]
+        ]);
+        assert_eq!(Some(grid), nonogram.solve());
+        assert!(nonogram.is_unique());
+
+        assert!("..#\n.x#\n".parse::<Grid>().is_err());
+        assert!("..#\n.#\n".parse::<Grid>().is_err());
+    }
+
+    #[test]
+    fn ambiguous_test() {
+        let nonogram = Nonogram::new(vec![vec![1], vec![1]], vec![vec![1], vec![1]]);
+        assert_eq!(2, nonogram.solutions(10).len());
+        assert!(!nonogram.is_unique());
+
+        let nonogram = Nonogram::new(vec![vec![2], vec![2]], vec![vec![1], vec![1]]);
+        assert_eq!(None, nonogram.solve());
+    }
+
+    #[test]
+    fn parse_test() {
+        let nonogram: Nonogram = "
+            title \"heart\"
+            width 5
+            height 4
+
+            rows
+            1,1
+            5
+            3
+            1
+
+            columns
+            1
+            3
+            3
+            3
+            1
+        "
+        .parse()
+        .unwrap();
+
+        let solution = nonogram.solve().unwrap();
+        assert_eq!(".#.#.\n#####\n.###.\n..#..\n", solution.to_string());
+
+        assert!("width 2\nrows\n1\ncolumns\n1\n"
+            .parse::<Nonogram>()
+            .is_err());
+        assert!("rows\n1,x\ncolumns\n1\n".parse::<Nonogram>().is_err());
+    }
+}
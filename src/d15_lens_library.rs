@@ -1,4 +1,6 @@
-use std::collections::HashMap;
+use std::{collections::BTreeMap, marker::PhantomData};
+
+use itertools::Itertools;
 
 use crate::utils::{Day, Task};
 
@@ -15,21 +17,288 @@ fn parse_command(s: &str) -> (String, Command) {
     }
 }
 
-struct Lens {
+#[derive(Clone)]
+struct Lens<V> {
     label: String,
-    focus: u32,
+    focus: V,
+}
+
+pub trait BoxHash {
+    fn box_count(&self) -> usize;
+    fn box_index(&self, label: &str) -> usize;
+}
+
+// Reduces before multiplying so that any multiplier and modulus fit in `u128`.
+fn fold_hash(values: impl Iterator<Item = u64>, multiplier: u64, modulus: u64) -> usize {
+    let (multiplier, modulus) = (multiplier as u128, modulus as u128);
+    values.fold(0, |acc, c| {
+        (acc + c as u128) % modulus * multiplier % modulus
+    }) as usize
+}
+
+// Where a hash reads its symbols from.
+pub trait Symbols {
+    fn symbols(label: &str) -> impl Iterator<Item = u64>;
+}
+
+#[derive(Copy, Clone)]
+pub struct Chars;
+
+impl Symbols for Chars {
+    fn symbols(label: &str) -> impl Iterator<Item = u64> {
+        label.chars().map(u64::from)
+    }
+}
+
+#[derive(Copy, Clone)]
+pub struct Bytes;
+
+impl Symbols for Bytes {
+    fn symbols(label: &str) -> impl Iterator<Item = u64> {
+        label.bytes().map(u64::from)
+    }
+}
+
+#[derive(Copy, Clone)]
+pub struct ModHash<S = Chars> {
+    multiplier: u64,
+    modulus: u64,
+    symbols: PhantomData<S>,
+}
+
+pub type HolidayHash = ModHash<Chars>;
+pub type ByteHash = ModHash<Bytes>;
+
+impl<S> ModHash<S> {
+    // `None` for a zero modulus, which would leave no boxes to hash into
+    pub fn new(multiplier: u64, modulus: u64) -> Option<Self> {
+        (modulus != 0).then_some(Self {
+            multiplier,
+            modulus,
+            symbols: PhantomData,
+        })
+    }
+}
+
+impl<S> Default for ModHash<S> {
+    fn default() -> Self {
+        Self::new(17, 256).unwrap()
+    }
 }
 
-impl Lens {
-    fn new(label: String, f: u32) -> Self {
-        Self { label, focus: f }
+impl<S: Symbols> BoxHash for ModHash<S> {
+    fn box_count(&self) -> usize {
+        self.modulus as usize
+    }
+
+    fn box_index(&self, label: &str) -> usize {
+        fold_hash(S::symbols(label), self.multiplier, self.modulus)
     }
 }
 
 fn hash(line: &str) -> u32 {
-    line.chars()
-        .map(u32::from)
-        .fold(0, |acc, c| (acc + c) * 17 % 256)
+    HolidayHash::default().box_index(line) as u32
+}
+
+#[derive(Clone)]
+pub struct LensBoxes<V = u32, H = HolidayHash> {
+    // only non-empty boxes are stored, so any modulus fits
+    boxes: BTreeMap<usize, Vec<Lens<V>>>,
+    hasher: H,
+}
+
+impl<V> LensBoxes<V> {
+    pub fn new() -> Self {
+        Self::with_hasher(HolidayHash::default())
+    }
+}
+
+impl<V, H: BoxHash> LensBoxes<V, H> {
+    pub fn with_hasher(hasher: H) -> Self {
+        Self {
+            boxes: BTreeMap::new(),
+            hasher,
+        }
+    }
+
+    fn slot(&self, label: &str) -> (usize, Option<usize>) {
+        let n = self.hasher.box_index(label);
+        let slot = self
+            .boxes
+            .get(&n)
+            .and_then(|lenses| lenses.iter().position(|l| l.label == label));
+        (n, slot)
+    }
+
+    pub fn insert(&mut self, label: &str, focus: V) -> Option<V> {
+        let (n, slot) = self.slot(label);
+        let lenses = self.boxes.entry(n).or_default();
+        match slot {
+            Some(slot) => Some(std::mem::replace(&mut lenses[slot].focus, focus)),
+            None => {
+                let label = label.to_string();
+                lenses.push(Lens { label, focus });
+                None
+            }
+        }
+    }
+
+    pub fn remove(&mut self, label: &str) -> Option<V> {
+        let (n, slot) = self.slot(label);
+        let lenses = self.boxes.get_mut(&n)?;
+        let lens = lenses.remove(slot?);
+        if lenses.is_empty() {
+            self.boxes.remove(&n);
+        }
+        Some(lens.focus)
+    }
+
+    pub fn get(&self, label: &str) -> Option<&V> {
+        match self.slot(label) {
+            (n, Some(slot)) => Some(&self.boxes[&n][slot].focus),
+            (_, None) => None,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.boxes.values().map(Vec::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.boxes.is_empty()
+    }
+
+    // (box, slot, label, focus) in box/slot order
+    pub fn iter(&self) -> impl Iterator<Item = (usize, usize, &str, &V)> {
+        self.boxes.iter().flat_map(|(&n, lenses)| {
+            lenses
+                .iter()
+                .enumerate()
+                .map(move |(slot, lens)| (n, slot, lens.label.as_str(), &lens.focus))
+        })
+    }
+}
+
+impl<H: BoxHash> LensBoxes<u32, H> {
+    pub fn apply(&mut self, step: &str) {
+        match parse_command(step) {
+            (label, Command::Remove) => self.remove(&label),
+            (label, Command::Put(focus)) => self.insert(&label, focus),
+        };
+    }
+
+    pub fn focusing_power(&self) -> u32 {
+        self.iter()
+            .map(|(n, slot, _, focus)| (1 + n as u32) * (1 + slot as u32) * focus)
+            .sum()
+    }
+}
+
+impl<V> Default for LensBoxes<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<V: std::fmt::Display, H> std::fmt::Display for LensBoxes<V, H> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (n, lenses) in &self.boxes {
+            write!(f, "Box {n}:")?;
+            for Lens { label, focus } in lenses {
+                write!(f, " [{label} {focus}]")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+pub struct LoadReport {
+    boxes: usize,
+    // distinct labels per used box
+    loads: BTreeMap<usize, usize>,
+}
+
+impl LoadReport {
+    pub fn new(sequence: &str, hasher: &impl BoxHash) -> Self {
+        let mut loads = BTreeMap::new();
+        sequence
+            .split(',')
+            .map(|step| parse_command(step).0)
+            .unique()
+            .for_each(|label| *loads.entry(hasher.box_index(&label)).or_default() += 1);
+
+        Self {
+            boxes: hasher.box_count(),
+            loads,
+        }
+    }
+
+    pub fn load(&self, n: usize) -> usize {
+        self.loads.get(&n).copied().unwrap_or(0)
+    }
+
+    pub fn used_boxes(&self) -> usize {
+        self.loads.len()
+    }
+
+    pub fn max_load(&self) -> usize {
+        self.loads.values().copied().max().unwrap_or(0)
+    }
+
+    // pairs of distinct labels sharing a box
+    pub fn collisions(&self) -> usize {
+        self.loads
+            .values()
+            .map(|load| load * load.saturating_sub(1) / 2)
+            .sum()
+    }
+}
+
+impl std::fmt::Display for LoadReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "labels: {}, boxes used: {}/{}, max load: {}, colliding pairs: {}",
+            self.loads.values().sum::<usize>(),
+            self.used_boxes(),
+            self.boxes,
+            self.max_load(),
+            self.collisions()
+        )?;
+        let empty = self.boxes - self.used_boxes();
+        if empty > 0 {
+            writeln!(f, "load 0: {empty} boxes")?;
+        }
+        for (load, boxes) in self.loads.values().counts().into_iter().sorted() {
+            writeln!(f, "load {load}: {boxes} boxes")?;
+        }
+        Ok(())
+    }
+}
+
+pub fn replay(sequence: &str) -> impl Iterator<Item = (&str, LensBoxes)> {
+    sequence.split(',').scan(LensBoxes::new(), |boxes, step| {
+        boxes.apply(step);
+        Some((step, boxes.clone()))
+    })
+}
+
+pub fn trace(sequence: &str, steps: Option<usize>) -> String {
+    replay(sequence)
+        .take(steps.unwrap_or(usize::MAX))
+        .map(|(step, boxes)| format!("After \"{step}\":\n{boxes}\n"))
+        .collect()
+}
+
+pub fn print_trace(filename: &str, steps: Option<usize>) {
+    let line = crate::utils::read_lines(filename).next().unwrap();
+    print!("{}", trace(&line, steps));
+}
+
+pub fn print_load_report(filename: &str, hasher: &impl BoxHash) {
+    let line = crate::utils::read_lines(filename).next().unwrap();
+    print!("{}", LoadReport::new(&line, hasher));
 }
 
 fn part_1(filename: &str) -> u32 {
@@ -39,35 +308,9 @@ fn part_1(filename: &str) -> u32 {
 
 fn part_2(filename: &str) -> u32 {
     let line = crate::utils::read_lines(filename).next().unwrap();
-    line.split(',')
-        .map(parse_command)
-        .fold(
-            HashMap::<u32, Vec<Lens>>::new(),
-            |mut boxes, (label, command)| {
-                use Command::*;
-
-                let lens_box = boxes.entry(hash(&label)).or_default();
-                let slot = lens_box.iter().position(|l: &Lens| l.label == label);
-                match (command, slot) {
-                    (Remove, None) => {}
-                    (Remove, Some(slot)) => {
-                        lens_box.remove(slot);
-                    }
-                    (Put(focus), None) => lens_box.push(Lens::new(label, focus)),
-                    (Put(focus), Some(slot)) => lens_box[slot] = Lens::new(label, focus),
-                }
-
-                boxes
-            },
-        )
-        .iter()
-        .flat_map(|(n, lenses)| {
-            lenses
-                .iter()
-                .enumerate()
-                .map(move |(slot, lens)| (1 + n) * (1 + slot as u32) * lens.focus)
-        })
-        .sum()
+    let mut boxes = LensBoxes::new();
+    line.split(',').for_each(|step| boxes.apply(step));
+    boxes.focusing_power()
 }
 
 pub fn solution() -> Day<u32, u32> {
@@ -96,6 +339,140 @@ mod d15_tests {
         assert_eq!(1320, res);
     }
 
+    #[test]
+    fn lens_boxes_test() {
+        let mut boxes = LensBoxes::new();
+        assert!(boxes.is_empty());
+
+        assert_eq!(None, boxes.insert("rn", 1));
+        assert_eq!(None, boxes.insert("cm", 2));
+        assert_eq!(None, boxes.insert("qp", 3));
+        assert_eq!(Some(3), boxes.insert("qp", 4));
+        assert_eq!(Some(&4), boxes.get("qp"));
+        assert_eq!(Some(4), boxes.remove("qp"));
+        assert_eq!(None, boxes.remove("qp"));
+        assert_eq!(None, boxes.get("qp"));
+        assert_eq!(2, boxes.len());
+
+        let entries = boxes
+            .iter()
+            .map(|(n, slot, label, _)| (n, slot, label))
+            .collect::<Vec<_>>();
+        assert_eq!(vec![(0, 0, "rn"), (0, 1, "cm")], entries);
+    }
+
+    #[test]
+    fn lens_boxes_display_test() {
+        let line = crate::utils::read_lines(solution().part_2.examples[0])
+            .next()
+            .unwrap();
+        let mut boxes = LensBoxes::new();
+        line.split(',').for_each(|step| boxes.apply(step));
+
+        assert_eq!(
+            "Box 0: [rn 1] [cm 2]\nBox 3: [ot 7] [ab 5] [pc 6]\n",
+            boxes.to_string()
+        );
+        assert_eq!(145, boxes.focusing_power());
+    }
+
+    #[test]
+    fn hashers_test() {
+        assert_eq!(52, HolidayHash::default().box_index("HASH"));
+        assert_eq!(52, ByteHash::default().box_index("HASH"));
+        assert_ne!(
+            HolidayHash::default().box_index("é"),
+            ByteHash::default().box_index("é")
+        );
+
+        assert!(HolidayHash::new(17, 0).is_none());
+        assert!(ByteHash::new(17, 0).is_none());
+        let huge = HolidayHash::new(u64::MAX, 1000).unwrap();
+        let reduced = HolidayHash::new(u64::MAX % 1000, 1000).unwrap();
+        assert_eq!(reduced.box_index("HASH"), huge.box_index("HASH"));
+
+        let mut boxes = LensBoxes::with_hasher(HolidayHash::new(31, 16).unwrap());
+        let line = crate::utils::read_lines(solution().part_2.examples[0])
+            .next()
+            .unwrap();
+        line.split(',').for_each(|step| boxes.apply(step));
+        assert_eq!(16, boxes.hasher.box_count());
+        assert_eq!(Some(&7), boxes.get("ot"));
+        assert_eq!(5, boxes.len());
+    }
+
+    #[test]
+    fn load_report_test() {
+        let line = crate::utils::read_lines(solution().part_2.examples[0])
+            .next()
+            .unwrap();
+        let report = LoadReport::new(&line, &HolidayHash::default());
+
+        assert_eq!(
+            vec![2, 1, 0, 3],
+            (0..4).map(|n| report.load(n)).collect::<Vec<_>>()
+        );
+        assert_eq!(3, report.used_boxes());
+        assert_eq!(3, report.max_load());
+        assert_eq!(4, report.collisions());
+        assert_eq!(
+            "\
+labels: 6, boxes used: 3/256, max load: 3, colliding pairs: 4
+load 0: 253 boxes
+load 1: 1 boxes
+load 2: 1 boxes
+load 3: 1 boxes
+",
+            report.to_string()
+        );
+    }
+
+    #[test]
+    fn huge_modulus_test() {
+        let line = crate::utils::read_lines(solution().part_2.examples[0])
+            .next()
+            .unwrap();
+        let hasher = HolidayHash::new(31, 1 << 32).unwrap();
+
+        let report = LoadReport::new(&line, &hasher);
+        assert_eq!(1 << 32, report.boxes);
+        assert_eq!(6, report.used_boxes());
+        assert_eq!(0, report.collisions());
+
+        let mut boxes = LensBoxes::with_hasher(hasher);
+        line.split(',').for_each(|step| boxes.apply(step));
+        assert_eq!(5, boxes.len());
+        assert_eq!(5, boxes.boxes.len());
+        boxes.remove("ot");
+        assert_eq!(4, boxes.boxes.len());
+    }
+
+    #[test]
+    fn trace_test() {
+        let line = crate::utils::read_lines(solution().part_2.examples[0])
+            .next()
+            .unwrap();
+
+        let expected = "\
+After \"rn=1\":
+Box 0: [rn 1]
+
+After \"cm-\":
+Box 0: [rn 1]
+
+After \"qp=3\":
+Box 0: [rn 1]
+Box 1: [qp 3]
+
+";
+        assert_eq!(expected, trace(&line, Some(3)));
+        assert_eq!(11, replay(&line).count());
+
+        let (last_step, boxes) = replay(&line).last().unwrap();
+        assert_eq!("ot=7", last_step);
+        assert_eq!(145, boxes.focusing_power());
+    }
+
     #[test]
     fn p2_example_test() {
         let solution = solution();
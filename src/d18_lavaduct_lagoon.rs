@@ -3,33 +3,26 @@ use Dir::*;
 
 use crate::utils::{Day, Task};
 
+pub mod polygon;
+pub mod render;
+
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
-enum Dir {
+pub enum Dir {
     U,
     L,
     D,
     R,
 }
 
-impl Dir {
-    fn reverse(&self) -> Self {
-        match self {
-            U => D,
-            L => R,
-            D => U,
-            R => L,
-        }
-    }
-}
-
 #[derive(Debug)]
-struct Command {
+pub struct Command {
     dir: Dir,
     len: isize,
+    color: [u8; 3],
 }
 
 impl Command {
-    fn from_string_1(s: String) -> Option<Self> {
+    pub fn from_string_1(s: String) -> Option<Self> {
         let mut split = s.split_whitespace();
 
         let dir = match split.next()? {
@@ -40,12 +33,18 @@ impl Command {
             _ => return None,
         };
         let len = split.next()?.parse().ok()?;
+        // part 1 ignores the colour, missing or malformed ones render as black
+        let color = split
+            .next()
+            .and_then(Self::parse_color)
+            .unwrap_or([0, 0, 0]);
 
-        Some(Self { dir, len })
+        Some(Self { dir, len, color })
     }
 
-    fn from_string_2(s: String) -> Option<Self> {
+    pub fn from_string_2(s: String) -> Option<Self> {
         let hex = s.split_whitespace().nth(2)?;
+        let color = Self::parse_color(hex)?;
 
         let len = isize::from_str_radix(&hex[2..=6], 16).ok()?;
         let dir = match hex.chars().nth(7)? {
@@ -56,58 +55,26 @@ impl Command {
             _ => return None,
         };
 
-        Some(Self { dir, len })
+        Some(Self { dir, len, color })
     }
-}
 
-fn process_commands(mut commands: Vec<Command>) -> usize {
-    let is_clockwise = commands
-        .iter()
-        .circular_tuple_windows::<(_, _)>()
-        .map(|(c1, c2)| match (c1.dir, c2.dir) {
-            (L, U) | (U, R) | (R, D) | (D, L) => 1,
-            _ => -1,
-        })
-        .sum::<i32>()
-        > 0;
-
-    if !is_clockwise {
-        commands.iter_mut().for_each(|c| c.dir = c.dir.reverse());
+    // `(#rrggbb)`
+    fn parse_color(s: &str) -> Option<[u8; 3]> {
+        let hex = s.strip_prefix("(#")?.strip_suffix(')')?;
+        match hex.len() {
+            6 => {
+                let [_, r, g, b] = u32::from_str_radix(hex, 16).ok()?.to_be_bytes();
+                Some([r, g, b])
+            }
+            _ => None,
+        }
     }
+}
 
-    let horizontal_segments = commands
-        .iter()
-        .cycle()
-        .skip_while(|c| matches!(c.dir, L | R))
-        .take(1 + commands.len())
-        .tuple_windows::<(_, _, _)>()
-        .step_by(2);
-
-    let (area, _) = horizontal_segments.fold((0, 0), |(area, height), (prev, curr, next)| {
-        let height = match prev.dir {
-            U => height - prev.len,
-            D => height + prev.len,
-            _ => unreachable!(),
-        };
-        let len = curr.len;
-
-        let dy = match curr.dir {
-            R => -height,
-            L => height + 1,
-            _ => unreachable!(),
-        };
-
-        let dx = match (prev.dir, curr.dir, next.dir) {
-            (U, _, U) | (D, _, D) => len,
-            (U, R, D) | (D, L, U) => len + 1,
-            (D, R, U) | (U, L, D) => len - 1,
-            _ => unreachable!(),
-        };
-
-        (area + dx * dy, height)
-    });
-
-    area.unsigned_abs()
+fn process_commands(commands: Vec<Command>) -> usize {
+    polygon::Polygon::from_commands(&commands)
+        .unwrap()
+        .lattice_points()
 }
 
 fn part_1(filename: &str) -> usize {
@@ -124,6 +91,20 @@ fn part_2(filename: &str) -> usize {
     process_commands(commands)
 }
 
+pub fn print_picture(
+    filename: &str,
+    decode: fn(String) -> Option<Command>,
+    format: render::Format,
+) {
+    let commands = crate::utils::read_lines(filename)
+        .flat_map(decode)
+        .collect_vec();
+    match render::Picture::new(&commands, render::Scale::Auto) {
+        Ok(picture) => print!("{}", picture.render(format)),
+        Err(err) => eprintln!("invalid dig plan: {err:?}"),
+    }
+}
+
 pub fn solution() -> Day<usize, usize> {
     Day {
         part_1: Task {
@@ -0,0 +1,225 @@
+use itertools::Itertools;
+
+use super::{Command, Dir::*};
+
+pub type Point = (isize, isize);
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum PolygonError {
+    Empty,
+    // where the plan ends instead of the starting point
+    NotClosed(Point),
+    // indices of the first pair of crossing edges
+    SelfIntersecting(usize, usize),
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Orientation {
+    Clockwise,
+    CounterClockwise,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Location {
+    Inside,
+    Boundary,
+    Outside,
+}
+
+// Rectilinear lattice polygon, `y` grows downwards as in the dig plan.
+pub struct Polygon {
+    vertices: Vec<Point>,
+}
+
+impl Polygon {
+    pub fn from_commands(commands: &[Command]) -> Result<Self, PolygonError> {
+        let mut vertices = vec![];
+        let mut position = (0, 0);
+
+        for Command { dir, len, .. } in commands.iter().filter(|c| c.len != 0) {
+            vertices.push(position);
+            let (x, y) = position;
+            position = match dir {
+                U => (x, y - len),
+                L => (x - len, y),
+                D => (x, y + len),
+                R => (x + len, y),
+            };
+        }
+
+        if vertices.is_empty() {
+            return Err(PolygonError::Empty);
+        }
+        if position != (0, 0) {
+            return Err(PolygonError::NotClosed(position));
+        }
+
+        let polygon = Self { vertices };
+        match polygon.find_self_intersection() {
+            Some((i, j)) => Err(PolygonError::SelfIntersecting(i, j)),
+            None => Ok(polygon),
+        }
+    }
+
+    pub fn vertices(&self) -> &[Point] {
+        &self.vertices
+    }
+
+    fn edges(&self) -> impl Iterator<Item = (Point, Point)> + '_ {
+        self.vertices.iter().copied().circular_tuple_windows()
+    }
+
+    fn edge(&self, i: usize) -> (Point, Point) {
+        let next = (i + 1) % self.vertices.len();
+        (self.vertices[i], self.vertices[next])
+    }
+
+    fn signed_double_area(&self) -> isize {
+        self.edges()
+            .map(|((x1, y1), (x2, y2))| x1 * y2 - x2 * y1)
+            .sum()
+    }
+
+    pub fn area(&self) -> usize {
+        self.signed_double_area().unsigned_abs() / 2
+    }
+
+    pub fn orientation(&self) -> Orientation {
+        match self.signed_double_area() > 0 {
+            true => Orientation::Clockwise,
+            false => Orientation::CounterClockwise,
+        }
+    }
+
+    pub fn boundary_points(&self) -> usize {
+        self.edges()
+            .map(|((x1, y1), (x2, y2))| num::integer::gcd(x1.abs_diff(x2), y1.abs_diff(y2)))
+            .sum()
+    }
+
+    // Pick's theorem: A = I + B / 2 - 1
+    pub fn interior_points(&self) -> usize {
+        (self.signed_double_area().unsigned_abs() + 2 - self.boundary_points()) / 2
+    }
+
+    pub fn lattice_points(&self) -> usize {
+        self.interior_points() + self.boundary_points()
+    }
+
+    pub fn find_self_intersection(&self) -> Option<(usize, usize)> {
+        fn touch(
+            ((x1, y1), (x2, y2)): (Point, Point),
+            ((x3, y3), (x4, y4)): (Point, Point),
+        ) -> bool {
+            x1.min(x2) <= x3.max(x4)
+                && x3.min(x4) <= x1.max(x2)
+                && y1.min(y2) <= y3.max(y4)
+                && y3.min(y4) <= y1.max(y2)
+        }
+
+        fn direction(((x1, y1), (x2, y2)): (Point, Point)) -> Point {
+            ((x2 - x1).signum(), (y2 - y1).signum())
+        }
+
+        let n = self.vertices.len();
+        (0..n).tuple_combinations().find(|&(i, j)| {
+            let (a, b) = (self.edge(i), self.edge(j));
+            if j == i + 1 || (i == 0 && j == n - 1) {
+                // adjacent edges share a vertex and only overlap when doubling back
+                let (da, db) = (direction(a), direction(b));
+                da == (-db.0, -db.1)
+            } else {
+                touch(a, b)
+            }
+        })
+    }
+
+    pub fn locate(&self, (px, py): Point) -> Location {
+        let on_boundary = self.edges().any(|((x1, y1), (x2, y2))| {
+            (x1.min(x2)..=x1.max(x2)).contains(&px) && (y1.min(y2)..=y1.max(y2)).contains(&py)
+        });
+        if on_boundary {
+            return Location::Boundary;
+        }
+
+        let crossings = self
+            .edges()
+            .filter(|&((x1, y1), (_, y2))| x1 > px && (y1 > py) != (y2 > py))
+            .count();
+
+        match crossings % 2 {
+            1 => Location::Inside,
+            _ => Location::Outside,
+        }
+    }
+
+    pub fn contains(&self, point: Point) -> bool {
+        self.locate(point) != Location::Outside
+    }
+}
+
+#[cfg(test)]
+mod polygon_tests {
+    use super::*;
+
+    fn commands(plan: &str) -> Vec<Command> {
+        plan.split(", ")
+            .flat_map(|c| Command::from_string_1(c.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn example_test() {
+        let commands = crate::utils::read_lines(super::super::solution().part_1.examples[0])
+            .flat_map(Command::from_string_1)
+            .collect_vec();
+        let polygon = Polygon::from_commands(&commands).unwrap();
+
+        assert_eq!(42, polygon.area());
+        assert_eq!(38, polygon.boundary_points());
+        assert_eq!(24, polygon.interior_points());
+        assert_eq!(62, polygon.lattice_points());
+        assert_eq!(Orientation::Clockwise, polygon.orientation());
+
+        assert_eq!(Location::Boundary, polygon.locate((0, 0)));
+        assert_eq!(Location::Inside, polygon.locate((1, 1)));
+        assert_eq!(Location::Inside, polygon.locate((3, 5)));
+        assert_eq!(Location::Outside, polygon.locate((0, 3)));
+        assert_eq!(Location::Outside, polygon.locate((7, 0)));
+        assert!(polygon.contains((2, 3)));
+
+        let plain = Command::from_string_1("R 6".to_string()).unwrap();
+        assert_eq!((R, 6, [0, 0, 0]), (plain.dir, plain.len, plain.color));
+        let malformed = Command::from_string_1("R 6 #70c710".to_string()).unwrap();
+        assert_eq!(
+            (R, 6, [0, 0, 0]),
+            (malformed.dir, malformed.len, malformed.color)
+        );
+    }
+
+    #[test]
+    fn orientation_test() {
+        let square = Polygon::from_commands(&commands("U 2, L 2, D 2, R 2")).unwrap();
+        assert_eq!(Orientation::CounterClockwise, square.orientation());
+        assert_eq!(4, square.area());
+        assert_eq!(1, square.interior_points());
+        assert_eq!(9, square.lattice_points());
+    }
+
+    #[test]
+    fn errors_test() {
+        assert_eq!(Some(PolygonError::Empty), Polygon::from_commands(&[]).err());
+        assert_eq!(
+            Some(PolygonError::NotClosed((2, 1))),
+            Polygon::from_commands(&commands("R 2, D 1")).err()
+        );
+        assert_eq!(
+            Some(PolygonError::SelfIntersecting(0, 1)),
+            Polygon::from_commands(&commands("R 2, L 2")).err()
+        );
+        assert_eq!(
+            Some(PolygonError::SelfIntersecting(0, 3)),
+            Polygon::from_commands(&commands("R 2, D 2, L 1, U 3, L 1, D 1")).err()
+        );
+    }
+}
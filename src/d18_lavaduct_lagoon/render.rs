@@ -0,0 +1,270 @@
+use std::{collections::VecDeque, fmt::Write};
+
+use itertools::Itertools;
+
+use super::{
+    polygon::{Polygon, PolygonError},
+    Command,
+    Dir::*,
+};
+
+// plans wider or taller than this are compressed by `Scale::Auto`
+const MAX_EXACT_SIDE: usize = 1000;
+const OUTSIDE_COLOR: [u8; 3] = [0xff, 0xff, 0xff];
+const INTERIOR_COLOR: [u8; 3] = [0x40, 0x40, 0x40];
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Scale {
+    // one pixel per cubic meter
+    Exact,
+    // one pixel per distinct vertex coordinate and one per gap between them
+    Compressed,
+    Auto,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Format {
+    Svg,
+    Ppm,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Cell {
+    Outside,
+    Interior,
+    Trench([u8; 3]),
+}
+
+struct Axis {
+    // vertex coordinate and its pixel index
+    marks: Vec<(isize, usize)>,
+}
+
+impl Axis {
+    fn new(coords: impl IntoIterator<Item = isize>, scale: Scale) -> Self {
+        let coords = coords.into_iter().sorted_unstable().dedup().collect_vec();
+
+        let mut index = 0;
+        let marks = coords
+            .iter()
+            .enumerate()
+            .map(|(i, &c)| {
+                if i > 0 {
+                    let gap = c.abs_diff(coords[i - 1]);
+                    index += match scale {
+                        Scale::Compressed => 1 + (gap > 1) as usize,
+                        _ => gap,
+                    };
+                }
+                (c, index)
+            })
+            .collect();
+
+        Self { marks }
+    }
+
+    fn index(&self, coord: isize) -> usize {
+        let i = self
+            .marks
+            .binary_search_by_key(&coord, |&(c, _)| c)
+            .unwrap();
+        self.marks[i].1
+    }
+
+    fn len(&self) -> usize {
+        self.marks.last().map_or(0, |&(_, index)| index + 1)
+    }
+}
+
+type Pixel = (usize, usize);
+
+pub struct Picture {
+    // one cell wide margin of `Outside` around the plan
+    cells: Vec<Vec<Cell>>,
+    // trench segments in pixel coordinates
+    edges: Vec<(Pixel, Pixel, [u8; 3])>,
+}
+
+impl Picture {
+    pub fn new(commands: &[Command], scale: Scale) -> Result<Self, PolygonError> {
+        let polygon = Polygon::from_commands(commands)?;
+        let xs = polygon.vertices().iter().map(|&(x, _)| x);
+        let ys = polygon.vertices().iter().map(|&(_, y)| y);
+
+        let scale = match scale {
+            Scale::Auto => {
+                let side = |(min, max): (isize, isize)| min.abs_diff(max) + 1;
+                let width = side(xs.clone().minmax().into_option().unwrap());
+                let height = side(ys.clone().minmax().into_option().unwrap());
+                match width.max(height) > MAX_EXACT_SIDE {
+                    true => Scale::Compressed,
+                    false => Scale::Exact,
+                }
+            }
+            scale => scale,
+        };
+        let (xs, ys) = (Axis::new(xs, scale), Axis::new(ys, scale));
+
+        let mut position = (0, 0);
+        let edges = commands
+            .iter()
+            .map(|&Command { dir, len, color }| {
+                let (x, y) = position;
+                position = match dir {
+                    U => (x, y - len),
+                    L => (x - len, y),
+                    D => (x, y + len),
+                    R => (x + len, y),
+                };
+                let pixel = |(x, y)| (xs.index(x) + 1, ys.index(y) + 1);
+                (pixel((x, y)), pixel(position), color)
+            })
+            .collect_vec();
+
+        let mut cells = vec![vec![Cell::Interior; xs.len() + 2]; ys.len() + 2];
+        for &((x1, y1), (x2, y2), color) in &edges {
+            for row in &mut cells[y1.min(y2)..=y1.max(y2)] {
+                row[x1.min(x2)..=x1.max(x2)].fill(Cell::Trench(color));
+            }
+        }
+
+        let mut queue = VecDeque::from([(0_usize, 0_usize)]);
+        cells[0][0] = Cell::Outside;
+        while let Some((x, y)) = queue.pop_front() {
+            let neighbours = [
+                (x.wrapping_sub(1), y),
+                (x + 1, y),
+                (x, y.wrapping_sub(1)),
+                (x, y + 1),
+            ];
+            for (x, y) in neighbours {
+                let cell = cells.get_mut(y).and_then(|row| row.get_mut(x));
+                if let Some(cell) = cell.filter(|cell| **cell == Cell::Interior) {
+                    *cell = Cell::Outside;
+                    queue.push_back((x, y));
+                }
+            }
+        }
+
+        Ok(Self { cells, edges })
+    }
+
+    pub fn width(&self) -> usize {
+        self.cells[0].len()
+    }
+
+    pub fn height(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn to_ppm(&self) -> String {
+        let mut ppm = format!("P3\n{} {}\n255\n", self.width(), self.height());
+        for row in &self.cells {
+            let line = row
+                .iter()
+                .map(|cell| {
+                    let [r, g, b] = match cell {
+                        Cell::Outside => OUTSIDE_COLOR,
+                        Cell::Interior => INTERIOR_COLOR,
+                        Cell::Trench(color) => *color,
+                    };
+                    format!("{r} {g} {b}")
+                })
+                .join(" ");
+            writeln!(ppm, "{line}").unwrap();
+        }
+        ppm
+    }
+
+    pub fn to_svg(&self) -> String {
+        let hex = |[r, g, b]: [u8; 3]| format!("#{r:02x}{g:02x}{b:02x}");
+        let center = |(x, y): Pixel| format!("{}.5,{}.5", x, y);
+
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 {} {}\" shape-rendering=\"crispEdges\">\n",
+            self.width(),
+            self.height()
+        );
+        writeln!(
+            svg,
+            "<rect width=\"100%\" height=\"100%\" fill=\"{}\"/>",
+            hex(OUTSIDE_COLOR)
+        )
+        .unwrap();
+        writeln!(
+            svg,
+            "<polygon points=\"{}\" fill=\"{}\"/>",
+            self.edges
+                .iter()
+                .map(|&(from, _, _)| center(from))
+                .join(" "),
+            hex(INTERIOR_COLOR)
+        )
+        .unwrap();
+        for &((x1, y1), (x2, y2), color) in &self.edges {
+            writeln!(
+                svg,
+                "<line x1=\"{x1}.5\" y1=\"{y1}.5\" x2=\"{x2}.5\" y2=\"{y2}.5\" stroke=\"{}\" stroke-linecap=\"square\"/>",
+                hex(color)
+            )
+            .unwrap();
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    pub fn render(&self, format: Format) -> String {
+        match format {
+            Format::Svg => self.to_svg(),
+            Format::Ppm => self.to_ppm(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod render_tests {
+    use super::*;
+
+    fn example(decode: fn(String) -> Option<Command>) -> Vec<Command> {
+        crate::utils::read_lines(super::super::solution().part_1.examples[0])
+            .flat_map(decode)
+            .collect_vec()
+    }
+
+    fn count(picture: &Picture, f: impl Fn(&Cell) -> bool) -> usize {
+        picture
+            .cells
+            .iter()
+            .flatten()
+            .filter(|cell| f(cell))
+            .count()
+    }
+
+    #[test]
+    fn exact_test() {
+        let picture = Picture::new(&example(Command::from_string_1), Scale::Auto).unwrap();
+
+        assert_eq!((9, 12), (picture.width(), picture.height()));
+        assert_eq!(38, count(&picture, |c| matches!(c, Cell::Trench(_))));
+        assert_eq!(24, count(&picture, |c| c == &Cell::Interior));
+        assert_eq!(Cell::Trench([0x70, 0xc7, 0x10]), picture.cells[1][2]);
+
+        let ppm = picture.to_ppm();
+        assert!(ppm.starts_with("P3\n9 12\n255\n255 255 255 255 255 255"));
+        assert_eq!(3 + 12, ppm.lines().count());
+    }
+
+    #[test]
+    fn compressed_test() {
+        let commands = example(Command::from_string_2);
+        let picture = Picture::new(&commands, Scale::Auto).unwrap();
+
+        assert!(picture.width() < 20 && picture.height() < 20);
+        assert!(count(&picture, |c| c == &Cell::Interior) > 0);
+
+        let svg = picture.to_svg();
+        assert_eq!(commands.len(), svg.matches("<line").count());
+        assert!(svg.contains("stroke=\"#70c710\""));
+        assert!(svg.contains("fill=\"#404040\""));
+    }
+}
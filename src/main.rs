@@ -1,5 +1,11 @@
 fn main() {
-    run_day(23);
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let day_number = args.first().map_or(23, |day| day.parse().unwrap());
+
+    match args.get(1) {
+        None => run_day(day_number),
+        Some(mode) => run_mode(day_number, mode, &args[2..]),
+    }
 }
 
 fn run_day(day_number: usize) {
@@ -35,3 +41,74 @@ fn run_day(day_number: usize) {
     solution.run_part_1();
     solution.run_part_2();
 }
+
+fn run_mode(day_number: usize, mode: &str, args: &[String]) {
+    use advent_of_code_2023::*;
+
+    match (day_number, mode) {
+        (1, "csv" | "json") => {
+            use d01_trebuchet::{DigitScanner, ReportFormat, ENGLISH, NUMERALS};
+
+            let scanner = match args.first().map(String::as_str) {
+                Some("1") => DigitScanner::new(&NUMERALS),
+                _ => DigitScanner::new(NUMERALS.iter().chain(&ENGLISH)),
+            };
+            let format = match mode {
+                "csv" => ReportFormat::Csv,
+                _ => ReportFormat::Json,
+            };
+            d01_trebuchet::print_report(d01_trebuchet::solution().part_2.task, &scanner, format);
+        }
+        (3, "show") => {
+            d03_gear_ratios::print_schematic(d03_gear_ratios::solution().part_1.task);
+        }
+        (4, "tree") => {
+            let card = args.first().map_or(1, |card| card.parse().unwrap());
+            let depth = args.get(1).map(|depth| depth.parse().unwrap());
+            d04_scratchcards::print_tree(d04_scratchcards::solution().part_2.task, card, depth);
+        }
+        (7, "table") => {
+            use d07_camel_cards::Ruleset;
+
+            let rules = match args.first().map(String::as_str) {
+                Some("joker") => Ruleset::joker(),
+                _ => Ruleset::standard(),
+            };
+            d07_camel_cards::print_rankings(d07_camel_cards::solution().part_1.task, &rules);
+        }
+        (15, "trace") => {
+            let steps = args.first().map(|steps| steps.parse().unwrap());
+            d15_lens_library::print_trace(d15_lens_library::solution().part_2.task, steps);
+        }
+        (15, "load") => {
+            use d15_lens_library::HolidayHash;
+
+            let hasher = match args {
+                [multiplier, modulus] => {
+                    HolidayHash::new(multiplier.parse().unwrap(), modulus.parse().unwrap())
+                        .expect("modulus must be positive")
+                }
+                _ => HolidayHash::default(),
+            };
+            d15_lens_library::print_load_report(d15_lens_library::solution().part_2.task, &hasher);
+        }
+        (18, "svg" | "ppm") => {
+            use d18_lavaduct_lagoon::{render::Format, Command};
+
+            let decode: fn(String) -> Option<Command> = match args.first().map(String::as_str) {
+                Some("2") => Command::from_string_2,
+                _ => Command::from_string_1,
+            };
+            let format = match mode {
+                "svg" => Format::Svg,
+                _ => Format::Ppm,
+            };
+            d18_lavaduct_lagoon::print_picture(
+                d18_lavaduct_lagoon::solution().part_1.task,
+                decode,
+                format,
+            );
+        }
+        _ => unreachable!("unknown mode '{mode}' for day {day_number}"),
+    }
+}